@@ -1,8 +1,7 @@
 use super::ColorPicker;
-use crate::{Selection, ThemeConstraints};
-use anyhow::{anyhow, bail, Result};
-use float_cmp::approx_eq;
-use palette::{Clamp, IntoColor, Lch, RelativeContrast, Srgba};
+use crate::{util::ContrastModel, ColorSpace, Selection, ThemeConstraints};
+use anyhow::{anyhow, Result};
+use palette::{IntoColor, Srgba};
 use serde::{de::DeserializeOwned, Serialize};
 use std::fmt;
 
@@ -73,20 +72,39 @@ where
     ) -> (C, Option<anyhow::Error>) {
         let mut err = None;
 
-        // AAA
-        let res = self.pick_color(color.clone(), Some(7.0), grayscale, lighten);
+        let ThemeConstraints {
+            text_contrast_ratio,
+            min_text_contrast_ratio,
+            ..
+        } = self.constraints;
+
+        // requested
+        let res = self.pick_color(color.clone(), Some(text_contrast_ratio), grayscale, lighten);
         if let Ok(c) = res {
             return (c, err);
         } else if let Err(e) = res {
-            err = Some(anyhow!("AAA text contrast failed: {}", e));
+            err = Some(anyhow!(
+                "Text contrast {} failed: {}",
+                text_contrast_ratio,
+                e
+            ));
         }
 
-        // AA
-        let res = self.pick_color(color.clone(), Some(4.5), grayscale, lighten);
+        // minimum
+        let res = self.pick_color(
+            color.clone(),
+            Some(min_text_contrast_ratio),
+            grayscale,
+            lighten,
+        );
         if let Ok(c) = res {
             return (c, err);
         } else if let Err(e) = res {
-            err = Some(anyhow!("AA text contrast failed: {}", e));
+            err = Some(anyhow!(
+                "Minimum text contrast {} failed: {}",
+                min_text_contrast_ratio,
+                e
+            ));
         }
 
         let res = self.pick_color(color.clone(), None, grayscale, lighten);
@@ -107,60 +125,50 @@ where
         lighten: Option<bool>,
     ) -> Result<C> {
         let srgba: Srgba = color.clone().into();
-        let mut lch_color: Lch = srgba.into_color();
+        let ThemeConstraints {
+            contrast_model,
+            color_space,
+            contrast_tolerance,
+            ..
+        } = self.constraints;
 
         // set to grayscale
-        if grayscale {
-            lch_color.chroma = 0.0;
-        }
-
-        // lighten or darken
-        // TODO closed form solution using Lch color space contrast formula?
-        // for now do binary search...
+        let template = if grayscale {
+            color_space.grayscale(srgba)
+        } else {
+            srgba
+        };
+        let lightness = color_space.lightness(template);
 
         if let Some(contrast) = contrast {
-            let (min, max) = match lighten {
-                Some(b) if b => (lch_color.l, 100.0),
-                Some(_) => (0.0, lch_color.l),
-                None => (0.0, 100.0),
+            // lighten or darken, trying the direction with more room first if none is given
+            let directions = match lighten {
+                Some(b) => vec![b],
+                None if lightness < 50.0 => vec![true, false],
+                None => vec![false, true],
             };
-            let (mut l, mut r) = (min, max);
-
-            for _ in 0..100 {
-                let cur_guess_lightness = (l + r) / 2.0;
-                let mut cur_guess = lch_color;
-                cur_guess.l = cur_guess_lightness;
-                let cur_contrast = srgba.get_contrast_ratio(&cur_guess.into_color());
-                let contrast_dir = contrast > cur_contrast;
-                let lightness_dir = lch_color.l < cur_guess.l;
-                if approx_eq!(f32, contrast, cur_contrast, ulps = 4) {
-                    lch_color = cur_guess;
-                    break;
-                    // TODO fix
-                } else if lightness_dir && contrast_dir || !lightness_dir && !contrast_dir {
-                    l = cur_guess_lightness;
-                } else {
-                    r = cur_guess_lightness;
-                }
-            }
-
-            // clamp to valid value in range
-            lch_color.clamp_self();
-
-            // verify contrast
-            let actual_contrast = srgba.get_contrast_ratio(&lch_color.into_color());
-            if !approx_eq!(f32, contrast, actual_contrast, ulps = 4) {
-                bail!(
-                    "Failed to derive color with contrast {} from {:?}",
+            for lighter in directions {
+                if let Some(c) = solve_lightness(
+                    template,
+                    srgba,
                     contrast,
-                    color
-                );
+                    lighter,
+                    contrast_model,
+                    color_space,
+                    contrast_tolerance,
+                ) {
+                    return Ok(C::from(c));
+                }
             }
 
-            Ok(C::from(lch_color.into_color()))
+            Err(anyhow!(
+                "Failed to derive color with contrast {} from {:?}",
+                contrast,
+                color
+            ))
         } else {
             // maximize contrast if no constraint is given
-            if lch_color.l > 50.0 {
+            if lightness > 50.0 {
                 Ok(C::from(palette::named::BLACK.into_format().into_color()))
             } else {
                 Ok(C::from(palette::named::WHITE.into_format().into_color()))
@@ -168,3 +176,169 @@ where
         }
     }
 }
+
+/// find the lightness of `template` which has `contrast` against `bg` within `tolerance`
+/// the target luminance is solved for directly and converted to a lightness,
+/// which is then refined by bisection if gamut clipping or the contrast model make it inexact
+#[allow(clippy::too_many_arguments)]
+fn solve_lightness(
+    template: Srgba,
+    bg: Srgba,
+    contrast: f32,
+    lighter: bool,
+    contrast_model: ContrastModel,
+    color_space: ColorSpace,
+    tolerance: f32,
+) -> Option<Srgba> {
+    let y = contrast_model.target_luminance(bg, contrast, lighter)?;
+    let measure = |l: f32| {
+        let guess = color_space.with_lightness(template, l);
+        (guess, contrast_model.contrast(guess, bg))
+    };
+
+    let start = color_space.lightness(template);
+    let estimate = color_space.lightness(contrast_model.gray(y));
+    let (guess, guess_contrast) = measure(estimate);
+    if (guess_contrast - contrast).abs() <= tolerance {
+        return Some(guess);
+    }
+
+    // contrast grows from the lightness of the background towards the extreme
+    let (mut near, mut far) = if lighter {
+        (start, 100.0)
+    } else {
+        (start, 0.0)
+    };
+    if (estimate > start) == lighter {
+        if guess_contrast < contrast {
+            near = estimate;
+        } else {
+            far = estimate;
+        }
+    }
+    for _ in 0..32 {
+        let mid = (near + far) / 2.0;
+        let (guess, guess_contrast) = measure(mid);
+        if (guess_contrast - contrast).abs() <= tolerance {
+            return Some(guess);
+        } else if guess_contrast < contrast {
+            near = mid;
+        } else {
+            far = mid;
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BACKGROUNDS: [(f32, f32, f32); 6] = [
+        (0.0, 0.0, 0.0),
+        (0.118, 0.118, 0.118),
+        (0.5, 0.5, 0.5),
+        (0.933, 0.933, 0.933),
+        (0.58, 0.922, 0.922),
+        (0.8, 0.2, 0.3),
+    ];
+
+    fn picker(constraints: ThemeConstraints) -> Exact<Srgba> {
+        Exact::new(Selection::default(), constraints)
+    }
+
+    fn assert_reaches(constraints: ThemeConstraints, contrasts: &[f32]) {
+        for color_space in [ColorSpace::Lch, ColorSpace::Oklch] {
+            let constraints = ThemeConstraints {
+                color_space,
+                ..constraints
+            };
+            let picker = picker(constraints);
+            for (r, g, b) in BACKGROUNDS {
+                let bg = Srgba::new(r, g, b, 1.0);
+                for &contrast in contrasts {
+                    for lighten in [true, false] {
+                        let reachable = constraints
+                            .contrast_model
+                            .target_luminance(bg, contrast, lighten)
+                            .is_some();
+                        let res = picker.pick_color(bg, Some(contrast), true, Some(lighten));
+                        if let Ok(c) = res {
+                            let measured = constraints.contrast_model.contrast(c, bg);
+                            assert!(
+                                (measured - contrast).abs() <= constraints.contrast_tolerance,
+                                "{color_space:?} {bg:?} lighten {lighten}: requested {contrast}, measured {measured}"
+                            );
+                        } else {
+                            assert!(
+                                !reachable,
+                                "{color_space:?} {bg:?} lighten {lighten}: {contrast} is reachable but failed"
+                            );
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn wcag_contrast_within_tolerance() {
+        assert_reaches(ThemeConstraints::default(), &[1.1, 1.51, 3.0, 4.5, 7.0]);
+    }
+
+    #[test]
+    fn apca_contrast_within_tolerance() {
+        assert_reaches(ThemeConstraints::apca(), &[8.0, 15.0, 45.0, 60.0, 75.0]);
+    }
+
+    #[test]
+    fn dark_background_reaches_text_contrast() {
+        let bg = Srgba::new(0.118, 0.118, 0.118, 1.0);
+        for constraints in [ThemeConstraints::default(), ThemeConstraints::apca()] {
+            let c = picker(constraints)
+                .pick_color(bg, Some(constraints.text_contrast_ratio), true, None)
+                .unwrap();
+            let measured = constraints.contrast_model.contrast(c, bg);
+            assert!(
+                (measured - constraints.text_contrast_ratio).abs()
+                    <= constraints.contrast_tolerance
+            );
+        }
+    }
+
+    #[test]
+    fn unreachable_contrast_is_an_error() {
+        let gray = Srgba::new(0.5, 0.5, 0.5, 1.0);
+        let white = Srgba::new(1.0, 1.0, 1.0, 1.0);
+        let wcag = picker(ThemeConstraints::default());
+        assert!(wcag.pick_color(gray, Some(15.0), true, None).is_err());
+        assert!(wcag.pick_color(white, Some(1.5), true, Some(true)).is_err());
+
+        let apca = picker(ThemeConstraints::apca());
+        assert!(apca.pick_color(gray, Some(100.0), true, None).is_err());
+    }
+
+    #[test]
+    fn derived_themes_have_distinct_names() {
+        let selection = |accent: Srgba| Selection {
+            background: Srgba::new(0.1, 0.1, 0.12, 1.0),
+            primary_container: Srgba::new(0.15, 0.15, 0.17, 1.0),
+            secondary_container: Srgba::new(0.2, 0.2, 0.22, 1.0),
+            accent,
+            destructive: Srgba::new(0.9, 0.3, 0.3, 1.0),
+            warning: Srgba::new(0.9, 0.8, 0.3, 1.0),
+            success: Srgba::new(0.3, 0.8, 0.4, 1.0),
+            ..Default::default()
+        };
+        let derive = |accent: Srgba| {
+            Exact::new(selection(accent), ThemeConstraints::default())
+                .theme_derivation()
+                .derived
+        };
+        let blue = derive(Srgba::new(0.3, 0.5, 0.9, 1.0));
+        let pink = derive(Srgba::new(0.9, 0.4, 0.7, 1.0));
+        assert_ne!(blue.name, pink.name);
+        assert!(blue.name.ends_with("-dark"));
+        assert_eq!(blue.palette.name, blue.name);
+    }
+}
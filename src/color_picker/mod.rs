@@ -1,13 +1,15 @@
-use crate::{Component, Container, ContainerType, Derivation, Selection, Theme, ThemeConstraints};
+use crate::{
+    util::is_dark, Component, Container, ContainerType, Derivation, Selection, Theme,
+    ThemeConstraints,
+};
 use anyhow::{anyhow, Result};
-use palette::{IntoColor, Lcha, Shade, Srgba};
+use palette::Srgba;
 use serde::{de::DeserializeOwned, Serialize};
 use std::fmt;
 
 pub use exact::*;
 mod exact;
 
-// TODO derive palette from Selection?
 /// Color picker derives colors and theme elements
 pub trait ColorPicker<
     C: Into<Srgba> + From<Srgba> + Clone + fmt::Debug + Default + Serialize + DeserializeOwned,
@@ -91,16 +93,22 @@ pub trait ColorPicker<
         } = self.widget_derivation(self.get_selection().success);
         theme_errors.append(&mut errors);
 
+        let name = derived_name(background.base.clone().into(), accent.base.clone().into());
+        let mut theme = Theme::new(
+            name.clone(),
+            background,
+            primary,
+            secondary,
+            accent,
+            destructive,
+            warning,
+            success,
+        );
+        theme.palette = self.get_selection().to_palette();
+        theme.palette.name = name;
+
         Derivation {
-            derived: Theme::new(
-                background,
-                primary,
-                secondary,
-                accent,
-                destructive,
-                warning,
-                success,
-            ),
+            derived: theme,
             errors: theme_errors,
         }
     }
@@ -197,34 +205,19 @@ pub trait ColorPicker<
             divider_contrast_ratio,
             divider_gray_scale,
             lighten,
+            color_space,
             ..
         } = self.get_constraints();
 
         let mut errors = Vec::new();
 
         let rgba: Srgba = default.clone().into();
-        let lch = Lcha {
-            color: rgba.color.into_color(),
-            alpha: rgba.alpha,
-        };
 
         // TODO define constraints for different states...
         // & add color self methods and errors if these fail
-        let hover = if lighten {
-            lch.lighten(0.1)
-        } else {
-            lch.darken(0.1)
-        };
-
-        let pressed = if lighten {
-            hover.lighten(0.1)
-        } else {
-            hover.darken(0.1)
-        };
-        let pressed = C::from(Srgba {
-            color: pressed.color.into_color(),
-            alpha: pressed.alpha,
-        });
+        let shade = if lighten { 0.1 } else { -0.1 };
+        let hover = color_space.shade(rgba, shade);
+        let pressed = C::from(color_space.shade(hover, shade));
 
         // TODO is this actually a different color? or just outlined?
         let selected = default.clone();
@@ -261,10 +254,7 @@ pub trait ColorPicker<
         Derivation {
             derived: Component {
                 base: default,
-                hover: C::from(Srgba {
-                    color: hover.color.into_color(),
-                    alpha: hover.alpha,
-                }),
+                hover: C::from(hover),
                 pressed,
                 selected: selected.clone(),
                 selected_text: selected_text,
@@ -278,3 +268,13 @@ pub trait ColorPicker<
         }
     }
 }
+
+/// name of a derived theme from its background and accent, so different derivations are saved to different files
+fn derived_name(background: Srgba, accent: Srgba) -> String {
+    let hex = |c: Srgba| {
+        let c: palette::rgb::Rgba<palette::encoding::Srgb, u8> = c.into_format();
+        format!("{:02x}{:02x}{:02x}", c.red, c.green, c.blue)
+    };
+    let variant = if is_dark(background) { "dark" } else { "light" };
+    format!("custom-{}-{}-{variant}", hex(background), hex(accent))
+}
@@ -1,14 +1,16 @@
 // SPDX-License-Identifier: MPL-2.0-only
 
-use crate::{util::CssColor, Theme, NAME, THEME_DIR};
+use crate::{
+    serialization::{load_from_path, remove_other_formats, save_to_path},
+    util::CssColor,
+    FileThemeProvider, SerializationFormat, Theme, ThemeProvider, NAME,
+};
 use anyhow::{bail, Result, Context};
 use palette::Srgba;
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use std::{
     fmt,
-    fs::File,
-    io::{prelude::*, BufReader},
-    path::PathBuf,
+    path::{Path, PathBuf},
 };
 use directories::{ProjectDirsExt, BaseDirsExt};
 
@@ -51,17 +53,44 @@ impl Config {
         }
     }
 
-    /// save the cosmic theme config
+    /// save the cosmic theme config, in the format of the existing config or RON
     pub fn save(&self) -> Result<()> {
+        let format = match Self::find_config_file()? {
+            Some(path) => SerializationFormat::from_path(&path)?,
+            None => SerializationFormat::Ron,
+        };
+        self.save_as(format)
+    }
+
+    /// save the cosmic theme config in the given format
+    /// configs saved in other formats are removed, so the new config is the one which is loaded
+    pub fn save_as(&self, format: SerializationFormat) -> Result<()> {
         let xdg_dirs = directories::ProjectDirs::from_path(PathBuf::from(NAME)).context("Failed to find project directory.")?;
-        if let Ok(path) = xdg_dirs.place_config_file(PathBuf::from(format!("{CONFIG_NAME}.ron"))) {
-            let mut f = File::create(path)?;
-            let ron = ron::ser::to_string_pretty(&self, Default::default())?;
-            f.write_all(ron.as_bytes())?;
-            Ok(())
+        let file_name = format!("{CONFIG_NAME}.{}", format.extension());
+        if let Ok(path) = xdg_dirs.place_config_file(PathBuf::from(file_name)) {
+            save_to_path(self, &path)?;
         } else {
             bail!("failed to save theme config")
         }
+        remove_other_formats(&xdg_dirs, CONFIG_NAME, format)
+    }
+
+    /// path of the saved config, in the first supported format which exists
+    pub(crate) fn find_config_file() -> Result<Option<PathBuf>> {
+        let xdg_dirs = directories::ProjectDirs::from_path(PathBuf::from(NAME)).context("Failed to find project directory.")?;
+        Ok(SerializationFormat::ALL.iter().find_map(|format| {
+            xdg_dirs.find_config_file(PathBuf::from(format!("{CONFIG_NAME}.{}", format.extension())))
+        }))
+    }
+
+    /// save the cosmic theme config to a path, in the format matching its extension
+    pub fn save_to(&self, p: &dyn AsRef<Path>) -> Result<()> {
+        save_to_path(self, p.as_ref())
+    }
+
+    /// load a cosmic theme config from a path, in the format matching its extension
+    pub fn load_from(p: &dyn AsRef<Path>) -> Result<Self> {
+        load_from_path(p.as_ref())
     }
 
     /// init the config directory
@@ -80,68 +109,50 @@ impl Config {
         }
     }
 
-    /// load the cosmic theme config
+    /// load the cosmic theme config, in any supported format
+    /// a default config is saved if there is none
     pub fn load() -> Result<Self> {
         let xdg_dirs = directories::ProjectDirs::from_path(PathBuf::from(NAME)).context("Failed to find project directory.")?;
-        let path = xdg_dirs.config_dir();
-        std::fs::create_dir_all(&path)?;
-        let path = xdg_dirs.find_config_file(PathBuf::from(format!("{CONFIG_NAME}.ron")));
-        if path.is_none() {
-            let s = Self::default();
-            s.save()?;
+        std::fs::create_dir_all(xdg_dirs.config_dir())?;
+        if Self::find_config_file()?.is_none() {
+            Self::default().save()?;
         }
-        if let Some(path) = xdg_dirs.find_config_file(PathBuf::from(format!("{CONFIG_NAME}.ron"))) {
-            let mut f = File::open(&path)?;
-            let mut s = String::new();
-            f.read_to_string(&mut s)?;
-            Ok(ron::from_str(s.as_str())?)
-        } else {
-            anyhow::bail!("Failed to load config")
+        match Self::find_config_file()? {
+            Some(path) => load_from_path(&path),
+            None => anyhow::bail!("Failed to load config"),
         }
     }
 
     /// get the name of the active theme
     pub fn active_name(&self) -> Option<String> {
-        if self.is_dark && self.dark.is_empty() {
+        if self.is_dark && !self.dark.is_empty() {
             Some(self.dark.clone())
         } else if !self.is_dark && !self.light.is_empty() {
             Some(self.light.clone())
         } else {
             None
         }
-        // if *high_contrast {
-        //     if let Some(palette) = palette.take() {
-        //         // TODO enforce high contrast constraints
-        //         *palette = palette.to_high_contrast();
-        //         todo!()
-        //     }
-        // }
     }
 
     /// get the active theme
+    /// themes are looked up like `FileThemeProvider` does, in every supported format
     pub fn get_active(&self) -> anyhow::Result<Theme<CssColor>> {
-        let active = match self.active_name() {
-            Some(n) => n,
-            _ => anyhow::bail!("No configured active overrides"),
-        };
-        let css_path: PathBuf = [NAME, THEME_DIR].iter().collect();
-        let css_dirs = directories::ProjectDirs::from_path(PathBuf::from(css_path)).context("Failed to find project directory.")?;
-        let active_theme_path = match css_dirs.find_data_file(format!("{active}.ron")) {
-            Some(p) => p,
-            _ => anyhow::bail!("Could not find theme"),
-        };
-        match File::open(active_theme_path) {
-            Ok(active_theme_file) => {
-                let reader = BufReader::new(active_theme_file);
-                Ok(ron::de::from_reader::<_, Theme<CssColor>>(reader)?)
-            }
-            Err(_) => {
-                if self.is_dark {
-                    Ok(Theme::dark_default())
-                } else {
-                    Ok(Theme::light_default())
-                }
-            }
+        let active = self
+            .active_name()
+            .context("No configured active overrides")?;
+        let theme = ThemeProvider::<CssColor>::theme(&FileThemeProvider::new(), &active)?;
+        Ok(self.apply_high_contrast(theme))
+    }
+
+    /// the theme converted to high contrast, if high contrast is enabled and the theme isn't already
+    pub fn apply_high_contrast<C>(&self, theme: Theme<C>) -> Theme<C>
+    where
+        C: Clone + fmt::Debug + Default + Into<Srgba> + From<Srgba> + Serialize + DeserializeOwned,
+    {
+        if self.is_high_contrast && !theme.is_high_contrast {
+            theme.to_high_contrast()
+        } else {
+            theme
         }
     }
 
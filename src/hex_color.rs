@@ -24,7 +24,7 @@ impl Into<String> for Hex {
 
 impl fmt::Display for Hex {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "#{}", self)
+        write!(f, "#{}", self.hex_string)
     }
 }
 
@@ -0,0 +1,420 @@
+#[cfg(feature = "contrast-derivation")]
+use crate::ThemeConstraints;
+use crate::{
+    util::{apca_contrast, contrast_ratio, over, ContrastModel},
+    Component, Container, Theme,
+};
+use palette::Srgba;
+use serde::{de::DeserializeOwned, Serialize};
+use std::fmt;
+
+/// WCAG 2 conformance level
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum WcagLevel {
+    /// 4.5:1 for text, 3:1 for graphics
+    #[default]
+    AA,
+    /// 7:1 for text, 3:1 for graphics
+    AAA,
+}
+
+impl WcagLevel {
+    /// minimum contrast ratio for the usage
+    pub fn contrast_ratio(&self, usage: ContrastUsage) -> f32 {
+        match (self, usage) {
+            (Self::AA, ContrastUsage::Text) => 4.5,
+            (Self::AAA, ContrastUsage::Text) => 7.0,
+            (_, ContrastUsage::Graphic | ContrastUsage::Divider) => 3.0,
+        }
+    }
+
+    /// minimum APCA Lc for the usage, at a similar level of readability
+    pub fn apca_contrast(&self, usage: ContrastUsage) -> f32 {
+        match (self, usage) {
+            (Self::AA, ContrastUsage::Text) => 60.0,
+            (Self::AAA, ContrastUsage::Text) => 75.0,
+            (Self::AA, ContrastUsage::Graphic | ContrastUsage::Divider) => 30.0,
+            (Self::AAA, ContrastUsage::Graphic | ContrastUsage::Divider) => 45.0,
+        }
+    }
+}
+
+impl fmt::Display for WcagLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::AA => write!(f, "AA"),
+            Self::AAA => write!(f, "AAA"),
+        }
+    }
+}
+
+/// What a foreground color is used for
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ContrastUsage {
+    /// text and icons
+    Text,
+    /// non text elements which convey information
+    Graphic,
+    /// dividers, which only separate other elements
+    Divider,
+}
+
+/// Options for auditing the contrast of a theme
+#[derive(Copy, Clone, Debug)]
+pub struct AuditOptions {
+    /// WCAG 2 level every text pair has to meet
+    pub level: WcagLevel,
+    /// also require the APCA contrast of the level
+    pub apca: bool,
+    /// check disabled text, which WCAG exempts
+    pub include_disabled: bool,
+    /// WCAG 2 contrast ratio dividers have to meet
+    /// dividers only separate other elements, so the default is the divider contrast of the default theme constraints
+    pub divider_contrast_ratio: f32,
+    /// APCA Lc dividers have to meet
+    pub divider_apca_contrast: f32,
+}
+
+impl Default for AuditOptions {
+    fn default() -> Self {
+        Self {
+            level: WcagLevel::AA,
+            apca: false,
+            include_disabled: false,
+            divider_contrast_ratio: ContrastModel::Wcag2.divider_contrast(),
+            divider_apca_contrast: ContrastModel::Apca.divider_contrast(),
+        }
+    }
+}
+
+impl AuditOptions {
+    /// required WCAG 2 contrast ratio for the usage
+    pub fn contrast_ratio(&self, usage: ContrastUsage) -> f32 {
+        match usage {
+            ContrastUsage::Divider => self.divider_contrast_ratio,
+            _ => self.level.contrast_ratio(usage),
+        }
+    }
+
+    /// required APCA Lc for the usage
+    pub fn apca_contrast(&self, usage: ContrastUsage) -> f32 {
+        match usage {
+            ContrastUsage::Divider => self.divider_apca_contrast,
+            _ => self.level.apca_contrast(usage),
+        }
+    }
+
+    /// audit dividers with the divider contrast the theme was derived with
+    #[cfg(feature = "contrast-derivation")]
+    pub fn with_constraints(self, constraints: &ThemeConstraints) -> Self {
+        match constraints.contrast_model {
+            ContrastModel::Wcag2 => Self {
+                divider_contrast_ratio: constraints.divider_contrast_ratio,
+                ..self
+            },
+            ContrastModel::Apca => Self {
+                divider_apca_contrast: constraints.divider_contrast_ratio,
+                ..self
+            },
+        }
+    }
+}
+
+/// The measured contrast of a foreground and background pair of a theme
+#[derive(Clone, Debug)]
+pub struct ContrastCheck {
+    /// path of the foreground color in the theme, e.g. `primary.component.on`
+    pub foreground: String,
+    /// path of the background color in the theme, e.g. `primary.component.base`
+    pub background: String,
+    /// what the foreground is used for
+    pub usage: ContrastUsage,
+    /// measured WCAG 2 contrast ratio
+    pub contrast_ratio: f32,
+    /// required WCAG 2 contrast ratio
+    pub required_contrast_ratio: f32,
+    /// measured APCA Lc, negative for light text on a dark background
+    pub apca_contrast: Option<f32>,
+    /// required APCA Lc, in either polarity
+    pub required_apca_contrast: Option<f32>,
+}
+
+impl ContrastCheck {
+    /// whether the pair meets the WCAG 2 requirement
+    pub fn passes_wcag(&self) -> bool {
+        self.contrast_ratio >= self.required_contrast_ratio - ContrastModel::Wcag2.tolerance()
+    }
+
+    /// whether the pair meets the APCA requirement, true if APCA isn't checked
+    pub fn passes_apca(&self) -> bool {
+        match (self.apca_contrast, self.required_apca_contrast) {
+            (Some(lc), Some(required)) => lc.abs() >= required - ContrastModel::Apca.tolerance(),
+            _ => true,
+        }
+    }
+
+    /// whether the pair meets every requirement
+    pub fn passes(&self) -> bool {
+        self.passes_wcag() && self.passes_apca()
+    }
+}
+
+impl fmt::Display for ContrastCheck {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} on {}: {:.2}:1, requires {:.1}:1",
+            self.foreground, self.background, self.contrast_ratio, self.required_contrast_ratio
+        )?;
+        if let (Some(lc), Some(required)) = (self.apca_contrast, self.required_apca_contrast) {
+            write!(f, ", Lc {:.1}, requires Lc {:.0}", lc, required)?;
+        }
+        Ok(())
+    }
+}
+
+/// Report of the contrast of every foreground and background pair of a theme
+#[derive(Clone, Debug)]
+pub struct ContrastAudit {
+    /// the options the theme was audited with
+    pub options: AuditOptions,
+    /// every checked pair
+    pub checks: Vec<ContrastCheck>,
+}
+
+impl ContrastAudit {
+    /// the pairs which don't meet their requirements
+    pub fn failures(&self) -> impl Iterator<Item = &ContrastCheck> {
+        self.checks.iter().filter(|c| !c.passes())
+    }
+
+    /// whether every pair meets its requirements
+    pub fn passes(&self) -> bool {
+        self.checks.iter().all(ContrastCheck::passes)
+    }
+}
+
+impl fmt::Display for ContrastAudit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let failures = self.failures().count();
+        writeln!(
+            f,
+            "{} of {} pairs fail WCAG {}{}",
+            failures,
+            self.checks.len(),
+            self.options.level,
+            if self.options.apca { " or APCA" } else { "" }
+        )?;
+        for check in self.failures() {
+            writeln!(f, "  {check}")?;
+        }
+        Ok(())
+    }
+}
+
+impl<C> Theme<C>
+where
+    C: Clone + fmt::Debug + Default + Into<Srgba> + From<Srgba> + Serialize + DeserializeOwned,
+{
+    /// check every foreground and background pair of the theme
+    ///
+    /// Containers are checked against their base, components against their states.
+    /// Translucent colors are composited over the layers below them,
+    /// the accent and semantic components over the background container.
+    pub fn audit(&self, options: AuditOptions) -> ContrastAudit {
+        let mut auditor = Auditor {
+            options,
+            checks: Vec::new(),
+        };
+        let background: Srgba = self.background.base.clone().into();
+        auditor.container("background", &self.background);
+        auditor.container("primary", &self.primary);
+        auditor.container("secondary", &self.secondary);
+        auditor.component("accent", &self.accent, background);
+        auditor.component("success", &self.success, background);
+        auditor.component("destructive", &self.destructive, background);
+        auditor.component("warning", &self.warning, background);
+        ContrastAudit {
+            options,
+            checks: auditor.checks,
+        }
+    }
+}
+
+struct Auditor {
+    options: AuditOptions,
+    checks: Vec<ContrastCheck>,
+}
+
+impl Auditor {
+    fn container<C: Clone + Into<Srgba>>(&mut self, path: &str, container: &Container<C>) {
+        let base: Srgba = container.base.clone().into();
+        let base_path = format!("{path}.base");
+        self.check(
+            path,
+            "on",
+            &container.on,
+            &base_path,
+            base,
+            ContrastUsage::Text,
+        );
+        self.check(
+            path,
+            "divider",
+            &container.divider,
+            &base_path,
+            base,
+            ContrastUsage::Divider,
+        );
+        self.component(&format!("{path}.component"), &container.component, base);
+    }
+
+    /// `backdrop` is the opaque color the component is drawn on
+    fn component<C: Clone + Into<Srgba>>(
+        &mut self,
+        path: &str,
+        component: &Component<C>,
+        backdrop: Srgba,
+    ) {
+        let base = over(component.base.clone(), backdrop);
+        let selected = over(component.selected.clone(), backdrop);
+        let disabled = over(component.disabled.clone(), backdrop);
+        let base_path = format!("{path}.base");
+        self.check(
+            path,
+            "on",
+            &component.on,
+            &base_path,
+            base,
+            ContrastUsage::Text,
+        );
+        self.check(
+            path,
+            "divider",
+            &component.divider,
+            &base_path,
+            base,
+            ContrastUsage::Divider,
+        );
+        self.check(
+            path,
+            "selected_text",
+            &component.selected_text,
+            &format!("{path}.selected"),
+            selected,
+            ContrastUsage::Text,
+        );
+        if self.options.include_disabled {
+            self.check(
+                path,
+                "on_disabled",
+                &component.on_disabled,
+                &format!("{path}.disabled"),
+                disabled,
+                ContrastUsage::Text,
+            );
+        }
+    }
+
+    fn check<C: Clone + Into<Srgba>>(
+        &mut self,
+        path: &str,
+        field: &str,
+        fg: &C,
+        bg_path: &str,
+        bg: Srgba,
+        usage: ContrastUsage,
+    ) {
+        let options = self.options;
+        self.checks.push(ContrastCheck {
+            foreground: format!("{path}.{field}"),
+            background: bg_path.to_string(),
+            usage,
+            contrast_ratio: contrast_ratio(fg.clone(), bg),
+            required_contrast_ratio: options.contrast_ratio(usage),
+            apca_contrast: options.apca.then(|| apca_contrast(fg.clone(), bg)),
+            required_apca_contrast: options.apca.then(|| options.apca_contrast(usage)),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn disabled_text_is_only_checked_on_request() {
+        let theme = Theme::dark_default();
+        let is_disabled = |c: &ContrastCheck| c.foreground.ends_with("on_disabled");
+
+        let audit = theme.audit(AuditOptions::default());
+        assert!(!audit.checks.iter().any(is_disabled));
+
+        let audit = theme.audit(AuditOptions {
+            include_disabled: true,
+            ..Default::default()
+        });
+        assert_eq!(audit.checks.iter().filter(|c| is_disabled(c)).count(), 7);
+    }
+
+    #[test]
+    fn dividers_use_the_divider_contrast() {
+        let options = AuditOptions {
+            divider_contrast_ratio: 2.0,
+            ..Default::default()
+        };
+        let audit = Theme::light_default().audit(options);
+        let dividers: Vec<_> = audit
+            .checks
+            .iter()
+            .filter(|c| c.foreground.ends_with("divider"))
+            .collect();
+        assert_eq!(dividers.len(), 10);
+        for check in dividers {
+            assert_eq!(check.usage, ContrastUsage::Divider);
+            assert_eq!(check.required_contrast_ratio, 2.0);
+        }
+    }
+
+    #[test]
+    fn high_contrast_themes_pass_aaa() {
+        for theme in [Theme::dark_default(), Theme::light_default()] {
+            let audit = theme.to_high_contrast().audit(AuditOptions {
+                level: WcagLevel::AAA,
+                ..Default::default()
+            });
+            assert!(audit.passes(), "{audit}");
+        }
+    }
+
+    #[test]
+    fn failing_pairs_are_reported() {
+        let mut theme = Theme::light_default();
+        theme.primary.on = theme.primary.base.clone();
+        let audit = theme.audit(AuditOptions {
+            apca: true,
+            ..Default::default()
+        });
+        let failure = audit
+            .failures()
+            .find(|c| c.foreground == "primary.on")
+            .expect("primary.on should fail");
+        assert_eq!(failure.background, "primary.base");
+        assert_eq!(failure.contrast_ratio, 1.0);
+        assert_eq!(failure.required_contrast_ratio, 4.5);
+        assert_eq!(failure.apca_contrast, Some(0.0));
+        assert_eq!(failure.required_apca_contrast, Some(60.0));
+        assert!(!audit.passes());
+    }
+
+    #[cfg(feature = "contrast-derivation")]
+    #[test]
+    fn divider_contrast_follows_the_constraints() {
+        let options = AuditOptions::default().with_constraints(&ThemeConstraints::apca());
+        assert_eq!(options.divider_apca_contrast, 15.0);
+        assert_eq!(
+            options.divider_contrast_ratio,
+            ThemeConstraints::default().divider_contrast_ratio
+        );
+    }
+}
@@ -1,26 +1,148 @@
+use crate::util::{ContrastModel, Oklch};
+use palette::{Clamp, FromColor, IntoColor, Lch, Lcha, Shade, Srgb, Srgba};
+
 /// Cosmic theme custom constraints which are used to pick colors
+/// contrast values are WCAG 2 ratios or APCA Lc values, depending on the contrast model
 #[derive(Copy, Clone, Debug)]
 pub struct ThemeConstraints {
+    /// model used to measure contrast
+    pub contrast_model: ContrastModel,
+    /// color space in which lightness and chroma are adjusted
+    pub color_space: ColorSpace,
     /// requested contrast ratio for elevated surfaces
     pub elevated_contrast_ratio: f32,
     /// requested contrast ratio for dividers
     pub divider_contrast_ratio: f32,
     /// requested contrast ratio for text
     pub text_contrast_ratio: f32,
+    /// minimum contrast ratio for text, used if the requested ratio can't be met
+    pub min_text_contrast_ratio: f32,
+    /// largest accepted difference between the requested and the derived contrast
+    pub contrast_tolerance: f32,
     /// gray scale or color for dividers
     pub divider_gray_scale: bool,
     /// elevated surfaces are lightened or darkened
     pub lighten: bool,
 }
 
+impl ThemeConstraints {
+    /// default constraints expressed as APCA Lc values
+    pub fn apca() -> Self {
+        Self {
+            contrast_model: ContrastModel::Apca,
+            elevated_contrast_ratio: 8.0,
+            divider_contrast_ratio: ContrastModel::Apca.divider_contrast(),
+            text_contrast_ratio: 75.0,
+            min_text_contrast_ratio: 60.0,
+            contrast_tolerance: ContrastModel::Apca.tolerance(),
+            ..Default::default()
+        }
+    }
+}
+
 impl Default for ThemeConstraints {
     fn default() -> Self {
         Self {
+            contrast_model: ContrastModel::Wcag2,
+            color_space: ColorSpace::Lch,
             elevated_contrast_ratio: 1.1,
-            divider_contrast_ratio: 1.51,
+            divider_contrast_ratio: ContrastModel::Wcag2.divider_contrast(),
             text_contrast_ratio: 7.0,
+            min_text_contrast_ratio: 4.5,
+            contrast_tolerance: ContrastModel::Wcag2.tolerance(),
             divider_gray_scale: true,
             lighten: true,
         }
     }
 }
+
+/// Color space used by the color picker to derive colors
+/// lightness is always expressed from 0 to 100
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum ColorSpace {
+    /// CIE Lch
+    #[default]
+    Lch,
+    /// Oklch, which keeps the perceived hue and chroma of derived colors
+    Oklch,
+}
+
+impl ColorSpace {
+    /// lightness of the color
+    pub(crate) fn lightness(&self, c: Srgba) -> f32 {
+        match self {
+            Self::Lch => {
+                let lch: Lch = c.color.into_color();
+                lch.l
+            }
+            Self::Oklch => Oklch::from(c.color).l * 100.0,
+        }
+    }
+
+    /// the color with its lightness replaced, keeping its hue
+    /// the result is opaque and in the srgb gamut
+    pub(crate) fn with_lightness(&self, c: Srgba, l: f32) -> Srgba {
+        match self {
+            Self::Lch => {
+                let mut lch: Lch = c.color.into_color();
+                lch.l = l;
+                Srgb::from_color(lch).clamp().into()
+            }
+            Self::Oklch => {
+                let mut oklch = Oklch::from(c.color);
+                oklch.l = l / 100.0;
+                oklch.into_srgb_gamut().into()
+            }
+        }
+    }
+
+    /// the color with its chroma removed
+    pub(crate) fn grayscale(&self, c: Srgba) -> Srgba {
+        let color = match self {
+            Self::Lch => {
+                let mut lch: Lch = c.color.into_color();
+                lch.chroma = 0.0;
+                Srgb::from_color(lch)
+            }
+            Self::Oklch => Oklch {
+                chroma: 0.0,
+                ..Oklch::from(c.color)
+            }
+            .into_srgb_gamut(),
+        };
+        Srgba {
+            color,
+            alpha: c.alpha,
+        }
+    }
+
+    /// lighten the color by a fraction of its distance to white, or darken it for negative factors
+    pub(crate) fn shade(&self, c: Srgba, factor: f32) -> Srgba {
+        match self {
+            Self::Lch => {
+                let lch = Lcha {
+                    color: c.color.into_color(),
+                    alpha: c.alpha,
+                }
+                .lighten(factor);
+                Srgba {
+                    color: lch.color.into_color(),
+                    alpha: lch.alpha,
+                }
+            }
+            Self::Oklch => {
+                let mut oklch = Oklch::from(c.color);
+                let difference = if factor >= 0.0 {
+                    1.0 - oklch.l
+                } else {
+                    oklch.l
+                };
+                oklch.l = (oklch.l + difference.max(0.0) * factor).max(0.0);
+                Srgba {
+                    color: oklch.into_srgb_gamut(),
+                    alpha: c.alpha,
+                }
+            }
+        }
+    }
+}
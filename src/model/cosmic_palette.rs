@@ -1,7 +1,5 @@
 use std::{
     fmt,
-    fs::File,
-    io::Write,
     path::{Path, PathBuf},
 };
 
@@ -11,7 +9,11 @@ use lazy_static::lazy_static;
 use palette::Srgba;
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 
-use crate::{util::CssColor, NAME, PALETTE_DIR};
+use crate::{
+    serialization::{load_from_path, remove_other_formats, save_to_path},
+    util::CssColor,
+    SerializationFormat, NAME, PALETTE_DIR,
+};
 
 lazy_static! {
     /// built in light palette
@@ -35,6 +37,79 @@ pub enum CosmicPalette<C> {
     HighContrastDark(CosmicPaletteInner<C>),
 }
 
+/// Accent colors offered by the palette
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq, Eq, Hash)]
+pub enum AccentPreset {
+    /// blue of the built in palette
+    #[default]
+    Blue,
+    /// warm grey
+    WarmGrey,
+    /// orange
+    Orange,
+    /// yellow
+    Yellow,
+    /// purple
+    Purple,
+    /// pink
+    Pink,
+    /// indigo
+    Indigo,
+}
+
+impl AccentPreset {
+    /// every preset, in the order they should be displayed
+    pub const ALL: &'static [Self] = &[
+        Self::Blue,
+        Self::WarmGrey,
+        Self::Orange,
+        Self::Yellow,
+        Self::Purple,
+        Self::Pink,
+        Self::Indigo,
+    ];
+
+    /// color of the preset in the palette
+    /// blue is taken from the built in palette of the same variant,
+    /// because the blue of a palette is replaced by whichever accent was applied last
+    pub fn color<C>(&self, palette: &CosmicPaletteInner<C>, is_dark: bool) -> C
+    where
+        C: Clone + From<Srgba>,
+    {
+        match self {
+            Self::Blue => {
+                let builtin = if is_dark {
+                    DARK_PALETTE.as_ref()
+                } else {
+                    LIGHT_PALETTE.as_ref()
+                };
+                let blue: Srgba = builtin.blue.clone().into();
+                blue.into()
+            }
+            Self::WarmGrey => palette.accent_warm_grey.clone(),
+            Self::Orange => palette.accent_orange.clone(),
+            Self::Yellow => palette.accent_yellow.clone(),
+            Self::Purple => palette.accent_purple.clone(),
+            Self::Pink => palette.accent_pink.clone(),
+            Self::Indigo => palette.accent_indigo.clone(),
+        }
+    }
+}
+
+impl fmt::Display for AccentPreset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Blue => write!(f, "Blue"),
+            Self::WarmGrey => write!(f, "Warm Grey"),
+            Self::Orange => write!(f, "Orange"),
+            Self::Yellow => write!(f, "Yellow"),
+            Self::Purple => write!(f, "Purple"),
+            Self::Pink => write!(f, "Pink"),
+            Self::Indigo => write!(f, "Indigo"),
+        }
+    }
+}
+
 impl<C> AsRef<CosmicPaletteInner<C>> for CosmicPalette<C>
 where
     C: Clone + fmt::Debug + Default + Into<Srgba> + From<Srgba> + Serialize + DeserializeOwned,
@@ -193,6 +268,68 @@ impl From<CosmicPaletteInner<CssColor>> for CosmicPaletteInner<Srgba> {
     }
 }
 
+impl<C> CosmicPaletteInner<C>
+where
+    C: Into<Srgba>,
+{
+    /// convert the palette to another color type
+    pub fn into_color_type<D: From<Srgba>>(self) -> CosmicPaletteInner<D> {
+        let c = |c: C| D::from(c.into());
+        CosmicPaletteInner {
+            name: self.name,
+            blue: c(self.blue),
+            red: c(self.red),
+            green: c(self.green),
+            yellow: c(self.yellow),
+            gray_1: c(self.gray_1),
+            gray_2: c(self.gray_2),
+            gray_3: c(self.gray_3),
+            neutral_1: c(self.neutral_1),
+            neutral_2: c(self.neutral_2),
+            neutral_3: c(self.neutral_3),
+            neutral_4: c(self.neutral_4),
+            neutral_5: c(self.neutral_5),
+            neutral_6: c(self.neutral_6),
+            neutral_7: c(self.neutral_7),
+            neutral_8: c(self.neutral_8),
+            neutral_9: c(self.neutral_9),
+            neutral_10: c(self.neutral_10),
+            ext_warm_grey: c(self.ext_warm_grey),
+            ext_orange: c(self.ext_orange),
+            ext_yellow: c(self.ext_yellow),
+            ext_blue: c(self.ext_blue),
+            ext_purple: c(self.ext_purple),
+            ext_pink: c(self.ext_pink),
+            ext_indigo: c(self.ext_indigo),
+            accent_warm_grey: c(self.accent_warm_grey),
+            accent_orange: c(self.accent_orange),
+            accent_yellow: c(self.accent_yellow),
+            accent_purple: c(self.accent_purple),
+            accent_pink: c(self.accent_pink),
+            accent_indigo: c(self.accent_indigo),
+        }
+    }
+}
+
+impl<C> CosmicPalette<C>
+where
+    C: Into<Srgba>,
+{
+    /// convert the palette to another color type
+    pub fn into_color_type<D: From<Srgba>>(self) -> CosmicPalette<D> {
+        match self {
+            CosmicPalette::Dark(p) => CosmicPalette::Dark(p.into_color_type()),
+            CosmicPalette::Light(p) => CosmicPalette::Light(p.into_color_type()),
+            CosmicPalette::HighContrastLight(p) => {
+                CosmicPalette::HighContrastLight(p.into_color_type())
+            }
+            CosmicPalette::HighContrastDark(p) => {
+                CosmicPalette::HighContrastDark(p.into_color_type())
+            }
+        }
+    }
+}
+
 impl<C> CosmicPalette<C>
 where
     C: Clone + fmt::Debug + Default + Into<Srgba> + From<Srgba> + Serialize + DeserializeOwned,
@@ -206,20 +343,44 @@ where
             CosmicPalette::HighContrastDark(p) => &p.name,
         }
     }
+
+    /// the palette with a new accent color
+    /// use `Theme::from(palette).with_accent(accent)` to derive a theme with contrast guarantees for the accent
+    pub fn with_accent(&self, accent: C) -> Self {
+        let mut palette = self.clone();
+        match &mut palette {
+            CosmicPalette::Dark(p) => p.blue = accent,
+            CosmicPalette::Light(p) => p.blue = accent,
+            CosmicPalette::HighContrastLight(p) => p.blue = accent,
+            CosmicPalette::HighContrastDark(p) => p.blue = accent,
+        }
+        palette
+    }
+
     /// save the theme to the theme directory
     pub fn save(&self) -> anyhow::Result<()> {
+        self.save_as(SerializationFormat::Ron)
+    }
+
+    /// save the palette to the palette directory in the given format
+    /// files of the palette in other formats are removed
+    pub fn save_as(&self, format: SerializationFormat) -> anyhow::Result<()> {
         let ron_path: PathBuf = [NAME, PALETTE_DIR].iter().collect();
         let ron_dirs = directories::ProjectDirs::from_path(ron_path)
             .context("Failed to get project directories.")?;
-        let ron_name = format!("{}.ron", self.name());
+        let file_name = format!("{}.{}", self.name(), format.extension());
 
-        if let Ok(p) = ron_dirs.place_config_file(ron_name) {
-            let mut f = File::create(p)?;
-            f.write_all(ron::ser::to_string_pretty(self, Default::default())?.as_bytes())?;
+        if let Ok(p) = ron_dirs.place_config_file(file_name) {
+            save_to_path(self, &p)?;
         } else {
-            anyhow::bail!("Failed to write RON theme.");
+            anyhow::bail!("Failed to write {} palette.", format);
         }
-        Ok(())
+        remove_other_formats(&ron_dirs, self.name(), format)
+    }
+
+    /// save the palette to a path, in the format matching its extension
+    pub fn save_to(&self, p: &dyn AsRef<Path>) -> anyhow::Result<()> {
+        save_to_path(self, p.as_ref())
     }
 
     /// init the theme directory
@@ -230,23 +391,22 @@ where
     }
 
     /// load a theme by name
+    /// every enabled format is tried, starting with RON
     pub fn load_from_name(name: &str) -> anyhow::Result<Self> {
         let ron_path: PathBuf = [NAME, PALETTE_DIR].iter().collect();
         let ron_dirs = directories::ProjectDirs::from_path(ron_path)
             .context("Failed to get project directories.")?;
 
-        let ron_name = format!("{}.ron", name);
-        if let Some(p) = ron_dirs.find_config_file(ron_name) {
-            let f = File::open(p)?;
-            Ok(ron::de::from_reader(f)?)
-        } else {
-            anyhow::bail!("Failed to write RON theme.");
+        for format in SerializationFormat::ALL {
+            if let Some(p) = ron_dirs.find_config_file(format!("{}.{}", name, format.extension())) {
+                return load_from_path(&p);
+            }
         }
+        anyhow::bail!("Failed to find palette \"{}\".", name);
     }
 
-    /// load a theme by path
+    /// load a theme by path, in the format matching its extension
     pub fn load(p: &dyn AsRef<Path>) -> anyhow::Result<Self> {
-        let f = File::open(p)?;
-        Ok(ron::de::from_reader(f)?)
+        load_from_path(p.as_ref())
     }
 }
@@ -2,7 +2,10 @@ use palette::Srgba;
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use std::fmt;
 
-use crate::{util::over, CosmicPalette};
+use crate::{
+    util::{contrast_ratio, ensure_contrast, is_dark, over},
+    CosmicPalette, HighContrastTargets,
+};
 
 /// Theme Container colors of a theme, can be a theme background container, primary container, or secondary container
 #[derive(Clone, Debug, Default, Deserialize, Serialize)]
@@ -31,6 +34,39 @@ where
         }
     }
 
+    pub(crate) fn to_high_contrast(&self, targets: &HighContrastTargets) -> Self {
+        let original: Srgba = self.base.clone().into();
+        let (on, base) = readable_pair(
+            self.on.clone().into(),
+            original,
+            targets.text_contrast_ratio,
+            None,
+        );
+        Self {
+            base: if base == original {
+                self.base.clone()
+            } else {
+                base.into()
+            },
+            component: self.component.to_high_contrast(targets, base),
+            divider: ensure_contrast(self.divider.clone(), base, targets.divider_contrast_ratio)
+                .into(),
+            on: on.into(),
+        }
+    }
+
+    pub(crate) fn with_accent(&self, accent: &C, text_ratio: f32, graphic_ratio: f32) -> Self {
+        Self {
+            component: self.component.with_accent(
+                accent,
+                self.base.clone().into(),
+                text_ratio,
+                graphic_ratio,
+            ),
+            ..self.clone()
+        }
+    }
+
     pub(crate) fn new(
         palette: CosmicPalette<C>,
         container_type: ComponentType,
@@ -226,6 +262,84 @@ where
         }
     }
 
+    /// `bg` is the opaque color the component is drawn on
+    /// The base and selected colors are lightened or darkened if no text color can reach the target on them.
+    pub(crate) fn to_high_contrast(&self, targets: &HighContrastTargets, bg: Srgba) -> Self {
+        let original = over(self.base.clone(), bg);
+        let (on, base) = readable_pair(
+            self.on.clone().into(),
+            original,
+            targets.text_contrast_ratio,
+            Some(bg),
+        );
+        let base_changed = base != original;
+
+        // state overlays use the text color, raise their opacity until they stand out enough
+        let mut overlay = on;
+        overlay.alpha = 0.1;
+        while overlay.alpha < 0.5 && contrast_ratio(overlay, base) < targets.state_contrast_ratio {
+            overlay.alpha += 0.05;
+        }
+        let mut pressed_overlay = overlay;
+        pressed_overlay.alpha = (overlay.alpha * 2.0).min(1.0);
+        let hover = over(overlay, base);
+        let pressed = over(pressed_overlay, base);
+        let (selected_text, selected) = readable_pair(
+            self.selected_text.clone().into(),
+            over(self.selected.clone(), bg),
+            targets.text_contrast_ratio,
+            Some(bg),
+        );
+        let selected = distinct_state(selected, selected_text, &[hover, pressed], targets);
+
+        // disabled components are the translucent base
+        let mut disabled: Srgba = self.disabled.clone().into();
+        if base_changed {
+            disabled.color = base.color;
+        }
+        let disabled_over_bg = over(disabled, bg);
+
+        Component {
+            base: if base_changed {
+                base.into()
+            } else {
+                self.base.clone()
+            },
+            hover: hover.into(),
+            pressed: pressed.into(),
+            selected: selected.into(),
+            selected_text: selected_text.into(),
+            focus: ensure_contrast(self.focus.clone(), base, targets.divider_contrast_ratio).into(),
+            divider: ensure_contrast(self.divider.clone(), base, targets.divider_contrast_ratio)
+                .into(),
+            on: on.into(),
+            disabled: disabled.into(),
+            on_disabled: ensure_contrast(
+                self.on_disabled.clone(),
+                disabled_over_bg,
+                targets.divider_contrast_ratio,
+            )
+            .into(),
+        }
+    }
+
+    /// selected text and focus colors from the accent, with enough contrast against the component drawn over `bg`
+    pub(crate) fn with_accent(
+        &self,
+        accent: &C,
+        bg: Srgba,
+        text_ratio: f32,
+        graphic_ratio: f32,
+    ) -> Self {
+        let base = over(self.base.clone(), bg);
+        let selected = over(self.selected.clone(), bg);
+        Self {
+            selected_text: ensure_contrast(accent.clone(), selected, text_ratio).into(),
+            focus: ensure_contrast(accent.clone(), base, graphic_ratio).into(),
+            ..self.clone()
+        }
+    }
+
     pub(crate) fn colored_component(base: C, neutral: C, accent: C) -> Self {
         let neutral = neutral.clone().into();
         let mut neutral_05 = neutral.clone();
@@ -305,6 +419,50 @@ where
     }
 }
 
+/// `fg` with at least `ratio` contrast against `bg`
+/// if even black or white can't reach it, `bg` is lightened or darkened away from the text instead,
+/// or away from the `surface` it is drawn on, so that it keeps standing out from it
+fn readable_pair(fg: Srgba, bg: Srgba, ratio: f32, surface: Option<Srgba>) -> (Srgba, Srgba) {
+    let readable = ensure_contrast(fg, bg, ratio);
+    if contrast_ratio(readable, bg) >= ratio {
+        return (readable, bg);
+    }
+    let text = match surface {
+        Some(surface) if is_dark(surface) => Srgba::new(0.0, 0.0, 0.0, 1.0),
+        Some(_) => Srgba::new(1.0, 1.0, 1.0, 1.0),
+        None => readable,
+    };
+    let bg = ensure_contrast(bg, text, ratio);
+    (ensure_contrast(fg, bg, ratio), bg)
+}
+
+/// `state` moved away from the text drawn on it until it stands out from the `other` states
+/// Moving away from the text keeps it readable.
+fn distinct_state(
+    state: Srgba,
+    text: Srgba,
+    other: &[Srgba],
+    targets: &HighContrastTargets,
+) -> Srgba {
+    let mut away = if is_dark(text) {
+        Srgba::new(1.0, 1.0, 1.0, 1.0)
+    } else {
+        Srgba::new(0.0, 0.0, 0.0, 1.0)
+    };
+    let mut res = state;
+    for step in 1..=10 {
+        if other
+            .iter()
+            .all(|o| contrast_ratio(res, *o) >= targets.state_contrast_ratio)
+        {
+            break;
+        }
+        away.alpha = step as f32 / 10.0;
+        res = over(away, state);
+    }
+    res
+}
+
 /// Derived theme element from a palette and constraints
 #[derive(Debug)]
 pub struct Derivation<E> {
@@ -1,3 +1,4 @@
+pub use audit::*;
 #[cfg(feature = "contrast-derivation")]
 pub use constraint::*;
 pub use cosmic_palette::*;
@@ -5,6 +6,7 @@ pub use derivation::*;
 #[cfg(feature = "contrast-derivation")]
 pub use selection::*;
 pub use theme::*;
+mod audit;
 #[cfg(feature = "contrast-derivation")]
 mod constraint;
 mod cosmic_palette;
@@ -1,5 +1,9 @@
-use palette::{named, IntoColor, Lch, Srgba};
-use std::convert::TryFrom;
+use crate::{
+    util::{is_dark, CssColor, Oklch},
+    CosmicPaletteInner, DARK_PALETTE, LIGHT_PALETTE,
+};
+use palette::Srgba;
+use std::{convert::TryFrom, f32::consts::PI, fmt};
 
 /// A Selection is a group of colors from which a cosmic palette can be derived
 #[derive(Copy, Clone, Debug, Default)]
@@ -24,76 +28,397 @@ pub struct Selection<C> {
     pub success: C,
 }
 
-// vector should be in order of most common
-impl<C> TryFrom<Vec<Srgba>> for Selection<C>
+impl<C> Selection<C>
 where
-    C: Clone + From<Srgba>,
+    C: Into<Srgba>,
 {
-    type Error = anyhow::Error;
+    /// convert the selection to another color type
+    pub fn into_color_type<D: From<Srgba>>(self) -> Selection<D> {
+        let c = |c: C| D::from(c.into());
+        Selection {
+            background: c(self.background),
+            primary_container: c(self.primary_container),
+            secondary_container: c(self.secondary_container),
+            accent: c(self.accent),
+            accent_fg: self.accent_fg.map(c),
+            accent_nav_handle_fg: self.accent_nav_handle_fg.map(c),
+            destructive: c(self.destructive),
+            warning: c(self.warning),
+            success: c(self.success),
+        }
+    }
+}
 
-    fn try_from(mut colors: Vec<Srgba>) -> Result<Self, Self::Error> {
-        if colors.len() < 8 {
-            anyhow::bail!("length of inputted vector must be at least 8.")
+impl<C> Selection<C>
+where
+    C: Clone + Into<Srgba> + From<Srgba>,
+{
+    /// derive a full palette from the selection
+    ///
+    /// The semantic colors and gray layers are taken from the selection.
+    /// The neutral ramp runs from black to white, or white to black for light backgrounds,
+    /// evenly spaced in Oklch lightness and tinted toward the background hue.
+    /// The extended and accent colors keep the hues of the default extended colors,
+    /// with the lightness and chroma of the selected semantic and accent colors.
+    pub fn to_palette(&self) -> CosmicPaletteInner<C> {
+        let oklch = |c: &C| -> Oklch { c.clone().into().color.into() };
+        let dark = is_dark(self.background.clone());
+        let (name, defaults) = if dark {
+            ("custom-dark", DARK_PALETTE.as_ref())
         } else {
-            let lch_colors: Vec<Lch> = colors
-                .iter()
-                .map(|x| {
-                    let srgba: Srgba = x.clone().into();
-                    srgba.color.into_format().into_color()
-                })
-                .collect();
-
-            let red_lch: Lch = named::CRIMSON.into_format().into_color();
-            let mut reddest_i = 1;
-            for (i, c) in lch_colors[1..].iter().enumerate() {
-                let d_cur = (c.hue.to_degrees() - red_lch.hue.to_degrees()).abs();
-                let reddest_d = (lch_colors[reddest_i].hue.to_degrees().abs()
-                    - red_lch.hue.to_degrees().abs())
-                .abs();
-                if d_cur < reddest_d {
-                    reddest_i = i;
-                }
-            }
+            ("custom-light", LIGHT_PALETTE.as_ref())
+        };
 
-            let yellow_lch: Lch = named::YELLOW.into_format().into_color();
-            let mut yellow_i = 1;
-            for (i, c) in lch_colors[1..].iter().enumerate() {
-                let d_cur = (c.hue.to_degrees() - yellow_lch.hue.to_degrees()).abs();
-                let reddest_d = (lch_colors[yellow_i].hue.to_degrees().abs()
-                    - yellow_lch.hue.to_degrees().abs())
-                .abs();
-                if d_cur < reddest_d {
-                    yellow_i = i;
-                }
-            }
+        // neutrals are pure black and white at the ends and most tinted in the middle
+        let background = oklch(&self.background);
+        let tint = background.chroma.min(0.04);
+        let neutral = |i: usize| -> C {
+            let t = i as f32 / 9.0;
+            let neutral = Oklch {
+                l: if dark { t } else { 1.0 - t },
+                chroma: tint * (PI * t).sin(),
+                hue: background.hue,
+            };
+            C::from(neutral.into_srgb_gamut().into())
+        };
+
+        let recolor = |template: &CssColor, l: f32, chroma: f32| -> C {
+            let template: Srgba = template.clone().into();
+            let template = Oklch::from(template.color);
+            let c = Oklch {
+                l,
+                chroma: chroma.min(template.chroma.max(0.02)),
+                hue: template.hue,
+            };
+            C::from(c.into_srgb_gamut().into())
+        };
+
+        let accent = oklch(&self.accent);
+        let semantic = [&self.destructive, &self.warning, &self.success].map(oklch);
+        let ext_l = semantic.iter().map(|c| c.l).sum::<f32>() / 3.0;
+        let ext_chroma = semantic.iter().map(|c| c.chroma).fold(0.0, f32::max);
+        let ext = |template: &CssColor| recolor(template, ext_l, ext_chroma);
+        let accent = |template: &CssColor| recolor(template, accent.l, accent.chroma);
+
+        CosmicPaletteInner {
+            name: name.to_string(),
+            blue: self.accent.clone(),
+            red: self.destructive.clone(),
+            green: self.success.clone(),
+            yellow: self.warning.clone(),
+            gray_1: self.background.clone(),
+            gray_2: self.primary_container.clone(),
+            gray_3: self.secondary_container.clone(),
+            neutral_1: neutral(0),
+            neutral_2: neutral(1),
+            neutral_3: neutral(2),
+            neutral_4: neutral(3),
+            neutral_5: neutral(4),
+            neutral_6: neutral(5),
+            neutral_7: neutral(6),
+            neutral_8: neutral(7),
+            neutral_9: neutral(8),
+            neutral_10: neutral(9),
+            ext_warm_grey: ext(&defaults.ext_warm_grey),
+            ext_orange: ext(&defaults.ext_orange),
+            ext_yellow: ext(&defaults.ext_yellow),
+            ext_blue: ext(&defaults.ext_blue),
+            ext_purple: ext(&defaults.ext_purple),
+            ext_pink: ext(&defaults.ext_pink),
+            ext_indigo: ext(&defaults.ext_indigo),
+            accent_warm_grey: accent(&defaults.ext_warm_grey),
+            accent_orange: accent(&defaults.ext_orange),
+            accent_yellow: accent(&defaults.ext_yellow),
+            accent_purple: accent(&defaults.ext_purple),
+            accent_pink: accent(&defaults.ext_pink),
+            accent_indigo: accent(&defaults.ext_indigo),
+        }
+    }
+}
+
+/// Role of a color in a selection
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum SelectionRole {
+    /// background container color
+    Background,
+    /// primary container color
+    PrimaryContainer,
+    /// secondary container color
+    SecondaryContainer,
+    /// accent color
+    Accent,
+    /// destructive element color
+    Destructive,
+    /// warning element color
+    Warning,
+    /// success element color
+    Success,
+}
+
+impl fmt::Display for SelectionRole {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Background => write!(f, "Background"),
+            Self::PrimaryContainer => write!(f, "Primary Container"),
+            Self::SecondaryContainer => write!(f, "Secondary Container"),
+            Self::Accent => write!(f, "Accent"),
+            Self::Destructive => write!(f, "Destructive"),
+            Self::Warning => write!(f, "Warning"),
+            Self::Success => write!(f, "Success"),
+        }
+    }
+}
+
+/// A selection assigned from a list of colors
+#[derive(Clone, Debug)]
+pub struct AssignedSelection<C> {
+    /// the selection
+    pub selection: Selection<C>,
+    /// roles without a suitable color in the list, which were synthesized or taken from the built in palette
+    pub synthesized: Vec<SelectionRole>,
+}
 
-            let green_lch: Lch = named::GREEN.into_format().into_color();
-            let mut green_i = 1;
-            for (i, c) in lch_colors[1..].iter().enumerate() {
-                let d_cur = (c.hue.to_degrees() - green_lch.hue.to_degrees()).abs();
-                let reddest_d = (lch_colors[green_i].hue.to_degrees().abs()
-                    - green_lch.hue.to_degrees().abs())
-                .abs();
-                if d_cur < reddest_d {
-                    green_i = i;
+/// colors with less Oklch chroma are treated as neutral
+const MIN_CHROMA: f32 = 0.04;
+/// largest hue distance in degrees of a semantic color from its reference hue
+const MAX_HUE_DISTANCE: f32 = 35.0;
+/// smallest Oklch lightness difference of a colored role from the background
+const MIN_LIGHTNESS_DISTANCE: f32 = 0.2;
+/// largest Oklch lightness difference of a container from the background
+const MAX_CONTAINER_DISTANCE: f32 = 0.12;
+
+impl<C> Selection<C>
+where
+    C: Clone + From<Srgba>,
+{
+    /// assign roles to a list of colors, which should be in order of most common
+    ///
+    /// The first color is the background.
+    /// Destructive, warning and success are the colors closest in hue to the red, yellow and green of the built in palette,
+    /// with enough chroma and lightness difference from the background.
+    /// The accent is the most common remaining color which stands out from the background,
+    /// and the containers are the most common remaining neutral colors close to the background in lightness.
+    /// Roles without a suitable color fall back to the built in palette, or to shades of the background for containers.
+    pub fn from_colors(colors: &[Srgba]) -> anyhow::Result<AssignedSelection<C>> {
+        let Some(&background) = colors.first() else {
+            anyhow::bail!("at least one color is required.")
+        };
+        let dark = is_dark(background);
+        let defaults = if dark {
+            DARK_PALETTE.as_ref()
+        } else {
+            LIGHT_PALETTE.as_ref()
+        };
+        let default = |c: &CssColor| -> Srgba { c.clone().into() };
+        let bg = Oklch::from(background.color);
+        let candidates: Vec<(Srgba, Oklch)> = colors[1..]
+            .iter()
+            .map(|c| (*c, Oklch::from(c.color)))
+            .collect();
+        let mut used = vec![false; candidates.len()];
+        let mut synthesized = Vec::new();
+
+        // semantic roles are assigned best match first, so a color is only used once
+        let semantic = [
+            (SelectionRole::Destructive, default(&defaults.red)),
+            (SelectionRole::Warning, default(&defaults.yellow)),
+            (SelectionRole::Success, default(&defaults.green)),
+        ];
+        let mut scores = Vec::new();
+        for (role_i, (_, reference)) in semantic.iter().enumerate() {
+            let reference = Oklch::from(reference.color);
+            for (i, (_, c)) in candidates.iter().enumerate() {
+                let hue_distance = hue_distance(c.hue, reference.hue);
+                if c.chroma < MIN_CHROMA
+                    || hue_distance > MAX_HUE_DISTANCE
+                    || (c.l - bg.l).abs() < MIN_LIGHTNESS_DISTANCE
+                {
+                    continue;
                 }
+                let score = hue_distance / MAX_HUE_DISTANCE
+                    + (1.0 - (c.chroma / reference.chroma).min(1.0))
+                    + (c.l - reference.l).abs();
+                scores.push((score, role_i, i));
+            }
+        }
+        scores.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let mut assigned: [Option<Srgba>; 3] = [None; 3];
+        for (_, role_i, i) in scores {
+            if assigned[role_i].is_none() && !used[i] {
+                assigned[role_i] = Some(candidates[i].0);
+                used[i] = true;
             }
+        }
+        let [destructive, warning, success] = [0, 1, 2].map(|role_i| {
+            let (role, default) = semantic[role_i];
+            assigned[role_i].unwrap_or_else(|| {
+                synthesized.push(role);
+                default
+            })
+        });
 
-            let red = colors.remove(reddest_i);
-            let green = colors.remove(green_i);
-            let yellow = colors.remove(yellow_i);
-
-            Ok(Self {
-                background: colors[0].into(),
-                primary_container: colors[1].into(),
-                secondary_container: colors[3].into(),
-                accent: colors[2].into(),
-                accent_fg: Some(colors[2].into()),
-                accent_nav_handle_fg: Some(colors[2].into()),
-                destructive: red.into(),
-                warning: yellow.into(),
-                success: green.into(),
+        let accent = candidates
+            .iter()
+            .enumerate()
+            .find(|(i, (_, c))| {
+                !used[*i] && c.chroma >= MIN_CHROMA && (c.l - bg.l).abs() >= MIN_LIGHTNESS_DISTANCE
+            })
+            .map(|(i, (c, _))| {
+                used[i] = true;
+                *c
             })
+            .unwrap_or_else(|| {
+                synthesized.push(SelectionRole::Accent);
+                default(&defaults.blue)
+            });
+
+        // containers are raised towards the text color, like the built in palettes
+        let mut container = |role: SelectionRole, step: f32| {
+            if let Some((i, (c, _))) = candidates.iter().enumerate().find(|(i, (_, c))| {
+                !used[*i]
+                    && c.chroma < MIN_CHROMA
+                    && (c.l - bg.l) * if dark { 1.0 } else { -1.0 } > 0.0
+                    && (c.l - bg.l).abs() <= MAX_CONTAINER_DISTANCE
+            }) {
+                used[i] = true;
+                return *c;
+            }
+            synthesized.push(role);
+            let shade = Oklch {
+                l: (bg.l + if dark { step } else { -step }).clamp(0.0, 1.0),
+                ..bg
+            };
+            Srgba {
+                color: shade.into_srgb_gamut(),
+                alpha: background.alpha,
+            }
+        };
+        let primary_container = container(SelectionRole::PrimaryContainer, 0.04);
+        let secondary_container = container(SelectionRole::SecondaryContainer, 0.08);
+
+        Ok(AssignedSelection {
+            selection: Self {
+                background: background.into(),
+                primary_container: primary_container.into(),
+                secondary_container: secondary_container.into(),
+                accent: accent.into(),
+                accent_fg: None,
+                accent_nav_handle_fg: None,
+                destructive: destructive.into(),
+                warning: warning.into(),
+                success: success.into(),
+            },
+            synthesized,
+        })
+    }
+}
+
+/// distance between two hues in degrees, going around the circle
+fn hue_distance(a: f32, b: f32) -> f32 {
+    let d = (a - b).rem_euclid(360.0);
+    d.min(360.0 - d)
+}
+
+// vector should be in order of most common
+impl<C> TryFrom<Vec<Srgba>> for Selection<C>
+where
+    C: Clone + From<Srgba>,
+{
+    type Error = anyhow::Error;
+
+    fn try_from(colors: Vec<Srgba>) -> Result<Self, Self::Error> {
+        Ok(Self::from_colors(&colors)?.selection)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn color(l: f32, chroma: f32, hue: f32) -> Srgba {
+        Oklch { l, chroma, hue }.into_srgb_gamut().into()
+    }
+
+    fn dark_background() -> Srgba {
+        DARK_PALETTE.as_ref().gray_1.clone().into()
+    }
+
+    #[test]
+    fn hue_distance_wraps_around() {
+        assert_eq!(hue_distance(350.0, 10.0), 20.0);
+        assert_eq!(hue_distance(10.0, 350.0), 20.0);
+        assert_eq!(hue_distance(0.0, 360.0), 0.0);
+        assert_eq!(hue_distance(90.0, 270.0), 180.0);
+    }
+
+    #[test]
+    fn destructive_is_found_on_either_side_of_zero() {
+        // the red of the dark palette has a hue of about 19 degrees
+        for hue in [355.0, 5.0] {
+            let red = color(0.8, 0.1, hue);
+            let orange = color(0.8, 0.1, 60.0);
+            let assigned =
+                Selection::<Srgba>::from_colors(&[dark_background(), orange, red]).unwrap();
+            assert_eq!(assigned.selection.destructive, red, "hue {hue}");
+            assert!(!assigned.synthesized.contains(&SelectionRole::Destructive));
         }
     }
+
+    #[test]
+    fn missing_roles_are_synthesized() {
+        assert!(Selection::<Srgba>::from_colors(&[]).is_err());
+
+        let background = dark_background();
+        let assigned = Selection::<Srgba>::from_colors(&[background]).unwrap();
+        assert_eq!(assigned.selection.background, background);
+        assert_eq!(
+            assigned.synthesized,
+            [
+                SelectionRole::Destructive,
+                SelectionRole::Warning,
+                SelectionRole::Success,
+                SelectionRole::Accent,
+                SelectionRole::PrimaryContainer,
+                SelectionRole::SecondaryContainer,
+            ]
+        );
+        let red: Srgba = DARK_PALETTE.as_ref().red.clone().into();
+        assert_eq!(assigned.selection.destructive, red);
+
+        let green = color(0.8, 0.1, 150.0);
+        let assigned = Selection::<Srgba>::from_colors(&[background, green]).unwrap();
+        assert_eq!(assigned.selection.success, green);
+        assert!(!assigned.synthesized.contains(&SelectionRole::Success));
+        assert!(assigned.synthesized.contains(&SelectionRole::Accent));
+    }
+
+    #[test]
+    fn try_from_assigns_like_from_colors() {
+        assert!(Selection::<Srgba>::try_from(Vec::new()).is_err());
+
+        let colors = vec![
+            dark_background(),
+            color(0.3, 0.01, 0.0),
+            color(0.6, 0.15, 250.0),
+            color(0.8, 0.1, 20.0),
+            color(0.9, 0.1, 100.0),
+            color(0.8, 0.1, 150.0),
+        ];
+        let assigned = Selection::<Srgba>::from_colors(&colors).unwrap().selection;
+        let selection = Selection::<Srgba>::try_from(colors.clone()).unwrap();
+        assert_eq!(selection.background, assigned.background);
+        assert_eq!(selection.primary_container, assigned.primary_container);
+        assert_eq!(selection.secondary_container, assigned.secondary_container);
+        assert_eq!(selection.accent, assigned.accent);
+        assert_eq!(selection.destructive, assigned.destructive);
+        assert_eq!(selection.warning, assigned.warning);
+        assert_eq!(selection.success, assigned.success);
+
+        // each color is used for at most one role
+        assert_eq!(selection.accent, colors[2]);
+        assert_eq!(selection.destructive, colors[3]);
+        assert_eq!(selection.warning, colors[4]);
+        assert_eq!(selection.success, colors[5]);
+        assert_eq!(selection.primary_container, colors[1]);
+    }
 }
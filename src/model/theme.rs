@@ -1,6 +1,8 @@
 use crate::{
-    util::CssColor, Component, ComponentType, Container, ContainerType, CosmicPalette,
-    CosmicPaletteInner, DARK_PALETTE, LIGHT_PALETTE, NAME, THEME_DIR,
+    serialization::{load_from_path, remove_other_formats, save_to_path},
+    util::{contrast_ratio, ensure_contrast, is_dark, over, ContrastModel, CssColor},
+    AccentPreset, Component, ComponentType, Container, ContainerType, CosmicPalette,
+    CosmicPaletteInner, SerializationFormat, DARK_PALETTE, LIGHT_PALETTE, NAME, THEME_DIR,
 };
 use anyhow::Context;
 use directories::{BaseDirsExt, ProjectDirsExt};
@@ -8,8 +10,6 @@ use palette::Srgba;
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use std::{
     fmt,
-    fs::File,
-    io::Write,
     path::{Path, PathBuf},
 };
 
@@ -52,6 +52,34 @@ pub struct Theme<C> {
     pub is_high_contrast: bool,
 }
 
+/// minimum contrast ratio of accent colored text
+const ACCENT_TEXT_CONTRAST: f32 = 4.5;
+/// minimum contrast ratio of accent colored focus indicators
+const ACCENT_GRAPHIC_CONTRAST: f32 = 3.0;
+/// suffix of the name of high contrast variants
+const HIGH_CONTRAST_SUFFIX: &str = "-high-contrast";
+
+/// Contrast targets used when converting a theme to a high contrast variant
+#[derive(Copy, Clone, Debug)]
+pub struct HighContrastTargets {
+    /// minimum contrast ratio of text against its container or component
+    pub text_contrast_ratio: f32,
+    /// minimum contrast ratio of dividers, focus rings and disabled text
+    pub divider_contrast_ratio: f32,
+    /// minimum contrast ratio of hover and selected states against the component
+    pub state_contrast_ratio: f32,
+}
+
+impl Default for HighContrastTargets {
+    fn default() -> Self {
+        Self {
+            text_contrast_ratio: 7.0,
+            divider_contrast_ratio: 3.0,
+            state_contrast_ratio: 1.3,
+        }
+    }
+}
+
 /// Trait for layered themes
 pub trait LayeredTheme {
     /// Set the layer of the theme
@@ -77,25 +105,244 @@ impl<C> Theme<C>
 where
     C: Clone + fmt::Debug + Default + Into<Srgba> + From<Srgba> + Serialize + DeserializeOwned,
 {
+    /// create a theme from derived containers and components
+    /// The theme is dark if white text has more contrast on the background than black text,
+    /// and high contrast if it meets the default high contrast targets.
+    /// It uses the default palette of the same variant.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        name: String,
+        background: Container<C>,
+        primary: Container<C>,
+        secondary: Container<C>,
+        accent: Component<C>,
+        destructive: Component<C>,
+        warning: Component<C>,
+        success: Component<C>,
+    ) -> Self {
+        let is_dark = is_dark(background.base.clone());
+        let palette = if is_dark {
+            DARK_PALETTE.as_ref()
+        } else {
+            LIGHT_PALETTE.as_ref()
+        };
+
+        let mut theme = Self {
+            name,
+            background,
+            primary,
+            secondary,
+            accent,
+            success,
+            destructive,
+            warning,
+            palette: palette.clone().into_color_type(),
+            is_dark,
+            is_high_contrast: false,
+        };
+        theme.is_high_contrast = theme.meets_high_contrast(&HighContrastTargets::default());
+        theme
+    }
+
     /// Convert the theme to a high-contrast variant
     pub fn to_high_contrast(&self) -> Self {
-        todo!();
+        self.to_high_contrast_with(HighContrastTargets::default())
+    }
+
+    /// Convert the theme to a high-contrast variant which meets the given targets
+    /// Foreground colors which already meet the targets are kept as is,
+    /// backgrounds are only lightened or darkened if no text color can reach the target on them.
+    /// The name is suffixed with `-high-contrast`, so saving the variant keeps the original theme.
+    pub fn to_high_contrast_with(&self, targets: HighContrastTargets) -> Self {
+        let background = self.background.to_high_contrast(&targets);
+        let bg: Srgba = background.base.clone().into();
+        let name = if self.name.ends_with(HIGH_CONTRAST_SUFFIX) {
+            self.name.clone()
+        } else {
+            format!("{}{HIGH_CONTRAST_SUFFIX}", self.name)
+        };
+        let mut theme = Self {
+            name,
+            primary: self.primary.to_high_contrast(&targets),
+            secondary: self.secondary.to_high_contrast(&targets),
+            accent: self.accent.to_high_contrast(&targets, bg),
+            success: self.success.to_high_contrast(&targets, bg),
+            destructive: self.destructive.to_high_contrast(&targets, bg),
+            warning: self.warning.to_high_contrast(&targets, bg),
+            background,
+            palette: self.palette.clone(),
+            is_dark: self.is_dark,
+            is_high_contrast: false,
+        };
+        theme.is_high_contrast = theme.meets_high_contrast(&targets);
+        theme
+    }
+
+    /// whether the text, selected text and dividers of every container and component meet the targets
+    fn meets_high_contrast(&self, targets: &HighContrastTargets) -> bool {
+        let tolerance = ContrastModel::Wcag2.tolerance();
+        let meets =
+            |fg: &C, bg: Srgba, ratio: f32| contrast_ratio(fg.clone(), bg) >= ratio - tolerance;
+        let component_meets = |c: &Component<C>, bg: Srgba| {
+            meets(&c.on, over(c.base.clone(), bg), targets.text_contrast_ratio)
+                && meets(
+                    &c.selected_text,
+                    over(c.selected.clone(), bg),
+                    targets.text_contrast_ratio,
+                )
+        };
+        let bg: Srgba = self.background.base.clone().into();
+        [&self.background, &self.primary, &self.secondary]
+            .iter()
+            .all(|c| {
+                let base: Srgba = c.base.clone().into();
+                meets(&c.on, base, targets.text_contrast_ratio)
+                    && meets(&c.divider, base, targets.divider_contrast_ratio)
+                    && component_meets(&c.component, base)
+            })
+            && [
+                &self.accent,
+                &self.success,
+                &self.destructive,
+                &self.warning,
+            ]
+            .iter()
+            .all(|c| component_meets(c, bg))
+    }
+
+    /// re-derive every accent dependent color of the theme from a new accent color
+    /// The accent is lightened or darkened until it has a contrast ratio of 3 against every container.
+    /// Selected text and focus indicators keep the accent hue, but are lightened or darkened where needed
+    /// to reach a contrast ratio of 4.5 for text and 3 for focus indicators against the components they are drawn on,
+    /// or the default high contrast targets for high contrast themes.
+    pub fn with_accent(&self, accent: C) -> Self {
+        let targets = HighContrastTargets::default();
+        let (text_ratio, graphic_ratio) = self.accent_contrast_ratios();
+        let bg: Srgba = self.background.base.clone().into();
+        let accent = self.accent_against_containers(accent, graphic_ratio);
+
+        // keep the text color of the accent component, as long as it can be read on the new accent
+        let on_accent =
+            ensure_contrast(self.accent.on.clone(), over(accent.clone(), bg), text_ratio);
+        let accent_component =
+            Component::colored_component(accent.clone(), on_accent.into(), accent.clone());
+
+        let mut palette = self.palette.clone();
+        palette.blue = accent.clone();
+
+        let theme = Self {
+            name: self.name.clone(),
+            background: self
+                .background
+                .with_accent(&accent, text_ratio, graphic_ratio),
+            primary: self.primary.with_accent(&accent, text_ratio, graphic_ratio),
+            secondary: self
+                .secondary
+                .with_accent(&accent, text_ratio, graphic_ratio),
+            accent: accent_component.with_accent(&accent, bg, text_ratio, graphic_ratio),
+            success: self
+                .success
+                .with_accent(&accent, bg, text_ratio, graphic_ratio),
+            destructive: self
+                .destructive
+                .with_accent(&accent, bg, text_ratio, graphic_ratio),
+            warning: self
+                .warning
+                .with_accent(&accent, bg, text_ratio, graphic_ratio),
+            palette,
+            is_dark: self.is_dark,
+            is_high_contrast: self.is_high_contrast,
+        };
+
+        // the new accent colors may not reach the high contrast targets on their own
+        if self.is_high_contrast {
+            Self {
+                name: self.name.clone(),
+                ..theme.to_high_contrast_with(targets)
+            }
+        } else {
+            theme
+        }
+    }
+
+    /// contrast ratios of accent colored text and graphics
+    fn accent_contrast_ratios(&self) -> (f32, f32) {
+        if self.is_high_contrast {
+            let targets = HighContrastTargets::default();
+            (targets.text_contrast_ratio, targets.divider_contrast_ratio)
+        } else {
+            (ACCENT_TEXT_CONTRAST, ACCENT_GRAPHIC_CONTRAST)
+        }
+    }
+
+    /// the accent, lightened or darkened until it has at least `ratio` contrast against every container
+    fn accent_against_containers(&self, accent: C, ratio: f32) -> C {
+        let bases: Vec<Srgba> = [&self.background, &self.primary, &self.secondary]
+            .iter()
+            .map(|c| c.base.clone().into())
+            .collect();
+        let mut accent: Srgba = accent.into();
+        // the containers are close in lightness, so moving away from the closest one rarely needs a second step
+        for _ in 0..bases.len() {
+            let closest = bases
+                .iter()
+                .copied()
+                .min_by(|a, b| contrast_ratio(accent, *a).total_cmp(&contrast_ratio(accent, *b)));
+            match closest {
+                Some(base) if contrast_ratio(accent, base) < ratio => {
+                    accent = ensure_contrast(accent, base, ratio);
+                }
+                _ => break,
+            }
+        }
+        accent.into()
+    }
+
+    /// every accent preset with the accent color it results in for the theme
+    /// The palette colors of the presets are lightened or darkened like in [`Theme::with_accent`].
+    pub fn accent_presets(&self) -> Vec<(AccentPreset, C)> {
+        let (_, graphic_ratio) = self.accent_contrast_ratios();
+        AccentPreset::ALL
+            .iter()
+            .map(|preset| {
+                let color = preset.color(&self.palette, self.is_dark);
+                (
+                    *preset,
+                    self.accent_against_containers(color, graphic_ratio),
+                )
+            })
+            .collect()
+    }
+
+    /// re-derive the theme with an accent preset
+    pub fn with_accent_preset(&self, preset: AccentPreset) -> Self {
+        self.with_accent(preset.color(&self.palette, self.is_dark))
     }
 
     /// save the theme to the theme directory
     pub fn save(&self) -> anyhow::Result<()> {
+        self.save_as(SerializationFormat::Ron)
+    }
+
+    /// save the theme to the theme directory in the given format
+    /// files of the theme in other formats are removed
+    pub fn save_as(&self, format: SerializationFormat) -> anyhow::Result<()> {
         let ron_path: PathBuf = [NAME, THEME_DIR].iter().collect();
         let ron_dirs = directories::ProjectDirs::from_path(ron_path)
             .context("Failed to get project directories.")?;
-        let ron_name = format!("{}.ron", &self.name);
+        let file_name = format!("{}.{}", &self.name, format.extension());
 
-        if let Ok(p) = ron_dirs.place_config_file(ron_name) {
-            let mut f = File::create(p)?;
-            f.write_all(ron::ser::to_string_pretty(self, Default::default())?.as_bytes())?;
+        if let Ok(p) = ron_dirs.place_config_file(file_name) {
+            save_to_path(self, &p)?;
         } else {
-            anyhow::bail!("Failed to write RON theme.");
+            anyhow::bail!("Failed to write {} theme.", format);
         }
-        Ok(())
+        remove_other_formats(&ron_dirs, &self.name, format)
+    }
+
+    /// save the theme to a path, in the format matching its extension
+    pub fn save_to(&self, p: &dyn AsRef<Path>) -> anyhow::Result<()> {
+        save_to_path(self, p.as_ref())
     }
 
     /// init the theme directory
@@ -106,24 +353,23 @@ where
     }
 
     /// load a theme by name
+    /// every enabled format is tried, starting with RON
     pub fn load_from_name(name: &str) -> anyhow::Result<Self> {
         let ron_path: PathBuf = [NAME, THEME_DIR].iter().collect();
         let ron_dirs = directories::ProjectDirs::from_path(ron_path)
             .context("Failed to get project directories.")?;
 
-        let ron_name = format!("{}.ron", name);
-        if let Some(p) = ron_dirs.find_config_file(ron_name) {
-            let f = File::open(p)?;
-            Ok(ron::de::from_reader(f)?)
-        } else {
-            anyhow::bail!("Failed to write RON theme.");
+        for format in SerializationFormat::ALL {
+            if let Some(p) = ron_dirs.find_config_file(format!("{}.{}", name, format.extension())) {
+                return load_from_path(&p);
+            }
         }
+        anyhow::bail!("Failed to find theme \"{}\".", name);
     }
 
-    /// load a theme by path
+    /// load a theme by path, in the format matching its extension
     pub fn load(p: &dyn AsRef<Path>) -> anyhow::Result<Self> {
-        let f = File::open(p)?;
-        Ok(ron::de::from_reader(f)?)
+        load_from_path(p.as_ref())
     }
 
     // TODO convenient getter functions for each named color variable
@@ -326,3 +572,80 @@ where
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::contrast_ratio;
+
+    #[test]
+    fn accent_presets_stand_out_from_the_background() {
+        for theme in [Theme::dark_default(), Theme::light_default()] {
+            let bg: Srgba = theme.background.base.clone().into();
+            for (preset, color) in theme.accent_presets() {
+                let ratio = contrast_ratio(color, bg);
+                assert!(
+                    ratio >= ACCENT_GRAPHIC_CONTRAST,
+                    "{preset} on {} has a contrast of {ratio}",
+                    theme.name
+                );
+
+                let accent = theme.with_accent_preset(preset).accent.base;
+                let ratio = contrast_ratio(accent, bg);
+                assert!(
+                    ratio >= ACCENT_GRAPHIC_CONTRAST,
+                    "{preset} accent on {} has a contrast of {ratio}",
+                    theme.name
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn high_contrast_meets_the_targets() {
+        let stricter = HighContrastTargets {
+            text_contrast_ratio: 10.0,
+            divider_contrast_ratio: 4.5,
+            state_contrast_ratio: 1.3,
+        };
+        for theme in [Theme::dark_default(), Theme::light_default()] {
+            for targets in [HighContrastTargets::default(), stricter] {
+                let hc = theme.to_high_contrast_with(targets);
+                assert!(hc.is_high_contrast, "{}", hc.name);
+                assert!(hc.meets_high_contrast(&targets), "{}", hc.name);
+            }
+            assert!(theme.to_high_contrast().is_high_contrast);
+        }
+    }
+
+    #[test]
+    fn high_contrast_states_are_distinct() {
+        for theme in [Theme::dark_default(), Theme::light_default()] {
+            let hc = theme.to_high_contrast().into_srgba();
+            let bg = hc.background.base;
+            let components = [
+                ("background", &hc.background.component, hc.background.base),
+                ("primary", &hc.primary.component, hc.primary.base),
+                ("secondary", &hc.secondary.component, hc.secondary.base),
+                ("accent", &hc.accent, bg),
+                ("success", &hc.success, bg),
+                ("destructive", &hc.destructive, bg),
+                ("warning", &hc.warning, bg),
+            ];
+            for (name, c, bg) in components {
+                let hover = over(c.hover, bg);
+                let pressed = over(c.pressed, bg);
+                let selected = over(c.selected, bg);
+                assert_ne!(hover, pressed, "{} {name}", hc.name);
+                for state in [hover, pressed] {
+                    let ratio = contrast_ratio(selected, state);
+                    assert!(
+                        ratio >= 1.29,
+                        "{} {name} selected has a contrast of {ratio} against another state",
+                        hc.name
+                    );
+                }
+            }
+        }
+    }
+}
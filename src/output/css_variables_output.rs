@@ -0,0 +1,226 @@
+use crate::{Component, Container, CosmicPaletteInner, Theme};
+use palette::Srgba;
+use serde::{de::DeserializeOwned, Serialize};
+use std::fmt;
+
+/// prefix of every css custom property
+pub const CSS_VARIABLE_PREFIX: &str = "cosmic";
+
+/// Trait for outputting the Theme as CSS custom properties or SCSS variables
+pub trait CssVariablesOutput {
+    /// every color of the theme with its variable name, without the `--cosmic-` prefix
+    /// names are the kebab case path of the color in the theme, e.g. `primary-component-hover`
+    fn css_variables(&self) -> Vec<(String, Srgba)>;
+    /// a `:root` block with a custom property for every color
+    fn as_css_properties(&self) -> String;
+    /// an SCSS map named `$name` with an entry for every color
+    fn as_scss_map(&self, name: &str) -> String;
+}
+
+impl<C> CssVariablesOutput for Theme<C>
+where
+    C: Clone + fmt::Debug + Default + Into<Srgba> + From<Srgba> + Serialize + DeserializeOwned,
+{
+    fn css_variables(&self) -> Vec<(String, Srgba)> {
+        let mut vars = Vec::new();
+        container_variables(&mut vars, "background", &self.background);
+        container_variables(&mut vars, "primary", &self.primary);
+        container_variables(&mut vars, "secondary", &self.secondary);
+        component_variables(&mut vars, "accent", &self.accent);
+        component_variables(&mut vars, "success", &self.success);
+        component_variables(&mut vars, "destructive", &self.destructive);
+        component_variables(&mut vars, "warning", &self.warning);
+        palette_variables(&mut vars, "palette", &self.palette);
+        vars
+    }
+
+    fn as_css_properties(&self) -> String {
+        format!(":root {{\n{}}}\n", css_declarations(self, "  "))
+    }
+
+    fn as_scss_map(&self, name: &str) -> String {
+        let mut scss = format!("${name}: (\n");
+        for (var, color) in self.css_variables() {
+            scss.push_str(&format!("  \"{var}\": {},\n", css_color(color)));
+        }
+        scss.push_str(");\n");
+        scss
+    }
+}
+
+/// custom properties for the light theme, overridden by the dark theme when the user prefers a dark color scheme
+pub fn css_color_scheme<C>(light: &Theme<C>, dark: &Theme<C>) -> String
+where
+    C: Clone + fmt::Debug + Default + Into<Srgba> + From<Srgba> + Serialize + DeserializeOwned,
+{
+    format!(
+        ":root {{\n  color-scheme: light dark;\n{}}}\n\n@media (prefers-color-scheme: dark) {{\n  :root {{\n{}  }}\n}}\n",
+        css_declarations(light, "  "),
+        css_declarations(dark, "    "),
+    )
+}
+
+fn css_declarations<T: CssVariablesOutput>(theme: &T, indent: &str) -> String {
+    let mut css = String::new();
+    for (var, color) in theme.css_variables() {
+        css.push_str(&format!(
+            "{indent}--{CSS_VARIABLE_PREFIX}-{var}: {};\n",
+            css_color(color)
+        ));
+    }
+    css
+}
+
+fn container_variables<C: Clone + Into<Srgba>>(
+    vars: &mut Vec<(String, Srgba)>,
+    prefix: &str,
+    container: &Container<C>,
+) {
+    let Container {
+        base,
+        component,
+        divider,
+        on,
+    } = container;
+    for (name, color) in [("base", base), ("divider", divider), ("on", on)] {
+        vars.push((format!("{prefix}-{name}"), color.clone().into()));
+    }
+    component_variables(vars, &format!("{prefix}-component"), component);
+}
+
+fn component_variables<C: Clone + Into<Srgba>>(
+    vars: &mut Vec<(String, Srgba)>,
+    prefix: &str,
+    component: &Component<C>,
+) {
+    let Component {
+        base,
+        hover,
+        pressed,
+        selected,
+        selected_text,
+        focus,
+        divider,
+        on,
+        disabled,
+        on_disabled,
+    } = component;
+    for (name, color) in [
+        ("base", base),
+        ("hover", hover),
+        ("pressed", pressed),
+        ("selected", selected),
+        ("selected-text", selected_text),
+        ("focus", focus),
+        ("divider", divider),
+        ("on", on),
+        ("disabled", disabled),
+        ("on-disabled", on_disabled),
+    ] {
+        vars.push((format!("{prefix}-{name}"), color.clone().into()));
+    }
+}
+
+fn palette_variables<C: Clone + Into<Srgba>>(
+    vars: &mut Vec<(String, Srgba)>,
+    prefix: &str,
+    p: &CosmicPaletteInner<C>,
+) {
+    for (name, color) in [
+        ("blue", &p.blue),
+        ("red", &p.red),
+        ("green", &p.green),
+        ("yellow", &p.yellow),
+        ("gray-1", &p.gray_1),
+        ("gray-2", &p.gray_2),
+        ("gray-3", &p.gray_3),
+        ("neutral-1", &p.neutral_1),
+        ("neutral-2", &p.neutral_2),
+        ("neutral-3", &p.neutral_3),
+        ("neutral-4", &p.neutral_4),
+        ("neutral-5", &p.neutral_5),
+        ("neutral-6", &p.neutral_6),
+        ("neutral-7", &p.neutral_7),
+        ("neutral-8", &p.neutral_8),
+        ("neutral-9", &p.neutral_9),
+        ("neutral-10", &p.neutral_10),
+        ("ext-warm-grey", &p.ext_warm_grey),
+        ("ext-orange", &p.ext_orange),
+        ("ext-yellow", &p.ext_yellow),
+        ("ext-blue", &p.ext_blue),
+        ("ext-purple", &p.ext_purple),
+        ("ext-pink", &p.ext_pink),
+        ("ext-indigo", &p.ext_indigo),
+        ("accent-warm-grey", &p.accent_warm_grey),
+        ("accent-orange", &p.accent_orange),
+        ("accent-yellow", &p.accent_yellow),
+        ("accent-purple", &p.accent_purple),
+        ("accent-pink", &p.accent_pink),
+        ("accent-indigo", &p.accent_indigo),
+    ] {
+        vars.push((format!("{prefix}-{name}"), color.clone().into()));
+    }
+}
+
+/// #rrggbb for opaque colors, #rrggbbaa otherwise
+fn css_color(c: Srgba) -> String {
+    let c: palette::rgb::Rgba<palette::encoding::Srgb, u8> = c.into_format();
+    if c.alpha == u8::MAX {
+        format!("#{:02x}{:02x}{:02x}", c.red, c.green, c.blue)
+    } else {
+        format!("#{:02x}{:02x}{:02x}{:02x}", c.red, c.green, c.blue, c.alpha)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::CssColor;
+    use std::collections::BTreeSet;
+
+    #[test]
+    fn every_color_has_a_unique_variable() {
+        for theme in [Theme::dark_default(), Theme::light_default()] {
+            let vars = theme.css_variables();
+            // 3 containers with 3 colors and a component, 4 components and 30 palette colors
+            assert_eq!(vars.len(), 3 * (3 + 10) + 4 * 10 + 30);
+            let names: BTreeSet<_> = vars.iter().map(|(name, _)| name.as_str()).collect();
+            assert_eq!(names.len(), vars.len());
+            for name in [
+                "background-base",
+                "primary-component-hover",
+                "secondary-component-selected-text",
+                "accent-on-disabled",
+                "palette-neutral-10",
+                "palette-accent-indigo",
+            ] {
+                assert!(names.contains(name), "{name} is missing");
+            }
+
+            let css = theme.as_css_properties();
+            assert!(css.starts_with(":root {\n"));
+            assert_eq!(css.matches("  --cosmic-").count(), vars.len());
+            let scss = theme.as_scss_map("cosmic");
+            assert!(scss.starts_with("$cosmic: (\n"));
+            assert_eq!(scss.matches("\": #").count(), vars.len());
+        }
+    }
+
+    #[test]
+    fn dark_colors_override_light_colors() {
+        let light = Theme::light_default();
+        let dark = Theme::dark_default();
+        let css = css_color_scheme(&light, &dark);
+        let (light_css, dark_css) = css
+            .split_once("@media (prefers-color-scheme: dark)")
+            .expect("dark media query");
+        let base = |theme: &Theme<CssColor>| {
+            format!(
+                "--cosmic-background-base: {};",
+                css_color(theme.background.base.clone().into())
+            )
+        };
+        assert!(light_css.contains(&base(&light)));
+        assert!(dark_css.contains(&base(&dark)));
+    }
+}
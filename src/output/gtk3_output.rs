@@ -0,0 +1,128 @@
+use super::write_user_config;
+use crate::{util::over, Theme};
+use anyhow::Result;
+use palette::Srgba;
+use serde::{de::DeserializeOwned, Serialize};
+use std::{fmt, path::PathBuf};
+
+/// name of the gtk3 user config directory
+pub const GTK3_CONFIG_DIR: &str = "gtk-3.0";
+
+/// Trait for outputting the Theme as gtk3 named colors
+pub trait Gtk3Output {
+    /// turn the theme into css which overrides the gtk3 named colors
+    fn as_gtk3_css(&self) -> String;
+    /// write the css to `gtk.css` in the gtk3 user config directory
+    fn write_gtk3(&self) -> Result<PathBuf>;
+}
+
+impl<C> Gtk3Output for Theme<C>
+where
+    C: Clone + fmt::Debug + Default + Into<Srgba> + From<Srgba> + Serialize + DeserializeOwned,
+{
+    fn as_gtk3_css(&self) -> String {
+        let Self {
+            background,
+            primary,
+            accent,
+            success,
+            destructive,
+            warning,
+            ..
+        } = self;
+        let c = |c: &C| gtk3_color(c.clone().into());
+        // gtk3 has no separate disabled surface, so flatten the translucent disabled colors
+        let bg: Srgba = background.base.clone().into();
+        let insensitive_bg = gtk3_color(over(background.component.disabled.clone(), bg));
+        let insensitive_fg = gtk3_color(over(background.component.on_disabled.clone(), bg));
+
+        let colors = [
+            ("theme_bg_color", c(&background.base)),
+            ("theme_fg_color", c(&background.on)),
+            ("theme_base_color", c(&primary.base)),
+            ("theme_text_color", c(&primary.on)),
+            ("theme_selected_bg_color", c(&accent.base)),
+            ("theme_selected_fg_color", c(&accent.on)),
+            ("insensitive_bg_color", insensitive_bg.clone()),
+            ("insensitive_fg_color", insensitive_fg.clone()),
+            ("insensitive_base_color", c(&primary.base)),
+            ("theme_unfocused_bg_color", c(&background.base)),
+            ("theme_unfocused_fg_color", c(&background.on)),
+            ("theme_unfocused_base_color", c(&primary.base)),
+            ("theme_unfocused_text_color", c(&primary.on)),
+            ("theme_unfocused_selected_bg_color", c(&accent.base)),
+            ("theme_unfocused_selected_fg_color", c(&accent.on)),
+            ("unfocused_insensitive_color", insensitive_fg),
+            ("borders", c(&background.divider)),
+            ("unfocused_borders", c(&background.divider)),
+            ("warning_color", c(&warning.base)),
+            ("error_color", c(&destructive.base)),
+            ("success_color", c(&success.base)),
+            ("content_view_bg", c(&primary.base)),
+        ];
+
+        let mut css = String::new();
+        for (name, color) in colors {
+            css.push_str(&format!("@define-color {name} {color};\n"));
+        }
+        css
+    }
+
+    fn write_gtk3(&self) -> Result<PathBuf> {
+        write_user_config(GTK3_CONFIG_DIR, "gtk.css", &self.as_gtk3_css())
+    }
+}
+
+/// gtk3 does not understand hex colors with alpha, so use the rgba() notation
+fn gtk3_color(c: Srgba) -> String {
+    let c: palette::rgb::Rgba<palette::encoding::Srgb, u8> = c.into_format();
+    format!(
+        "rgba({}, {}, {}, {:.3})",
+        c.red,
+        c.green,
+        c.blue,
+        c.alpha as f32 / 255.0
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_named_color_is_defined() {
+        for theme in [Theme::dark_default(), Theme::light_default()] {
+            let css = theme.as_gtk3_css();
+            for name in [
+                "theme_bg_color",
+                "theme_fg_color",
+                "theme_base_color",
+                "theme_text_color",
+                "theme_selected_bg_color",
+                "theme_selected_fg_color",
+                "insensitive_bg_color",
+                "insensitive_fg_color",
+                "insensitive_base_color",
+                "theme_unfocused_bg_color",
+                "theme_unfocused_fg_color",
+                "theme_unfocused_base_color",
+                "theme_unfocused_text_color",
+                "theme_unfocused_selected_bg_color",
+                "theme_unfocused_selected_fg_color",
+                "unfocused_insensitive_color",
+                "borders",
+                "unfocused_borders",
+                "warning_color",
+                "error_color",
+                "success_color",
+                "content_view_bg",
+            ] {
+                assert!(
+                    css.contains(&format!("@define-color {name} rgba(")),
+                    "{name} is missing"
+                );
+            }
+            assert!(css.lines().all(|l| l.ends_with(");")));
+        }
+    }
+}
@@ -1,14 +1,11 @@
-use crate::{
-    model::{Accent, Container, ContainerType, Destructive, Widget},
-    Hex, Theme, NAME,
-};
-use anyhow::{bail, Result};
+use crate::{Component, Container, Hex, Theme, NAME, THEME_DIR};
+use anyhow::{bail, Context, Result};
+use directories::ProjectDirsExt;
 use palette::Srgba;
 use serde::{de::DeserializeOwned, Serialize};
 use std::{fmt, fs::File, io::prelude::*, path::PathBuf};
 
-pub(crate) const CSS_DIR: &'static str = "css";
-pub(crate) const THEME_DIR: &'static str = "themes";
+pub(crate) const CSS_DIR: &str = "css";
 
 /// Trait for outputting the Theme as Gtk4CSS
 pub trait Gtk4Output {
@@ -21,14 +18,7 @@ pub trait Gtk4Output {
 
 impl<C> Gtk4Output for Theme<C>
 where
-    C: Clone
-        + fmt::Debug
-        + Default
-        + Into<Hex>
-        + Into<Srgba>
-        + From<Srgba>
-        + Serialize
-        + DeserializeOwned,
+    C: Clone + fmt::Debug + Default + Into<Srgba> + From<Srgba> + Serialize + DeserializeOwned,
 {
     fn as_css(&self) -> String {
         let Self {
@@ -36,16 +26,20 @@ where
             primary,
             secondary,
             accent,
+            success,
             destructive,
+            warning,
             ..
         } = self;
         let mut css = String::new();
 
-        css.push_str(&background.as_css());
-        css.push_str(&primary.as_css());
-        css.push_str(&secondary.as_css());
-        css.push_str(&accent.as_css());
-        css.push_str(&destructive.as_css());
+        css.push_str(&background.as_css("background"));
+        css.push_str(&primary.as_css("primary"));
+        css.push_str(&secondary.as_css("secondary"));
+        css.push_str(&accent.as_css("accent"));
+        css.push_str(&success.as_css("success"));
+        css.push_str(&destructive.as_css("destructive"));
+        css.push_str(&warning.as_css("warning"));
 
         css
     }
@@ -58,8 +52,10 @@ where
         let ron_path: PathBuf = [NAME, THEME_DIR].iter().collect();
         let css_path: PathBuf = [NAME, CSS_DIR].iter().collect();
 
-        let ron_dirs = xdg::BaseDirectories::with_prefix(ron_path)?;
-        let css_dirs = xdg::BaseDirectories::with_prefix(css_path)?;
+        let ron_dirs = directories::ProjectDirs::from_path(ron_path)
+            .context("Failed to get project directories.")?;
+        let css_dirs = directories::ProjectDirs::from_path(css_path)
+            .context("Failed to get project directories.")?;
 
         let ron_name = format!("{}.ron", &self.name);
         let css_name = format!("{}.css", &self.name);
@@ -75,7 +71,7 @@ where
             let mut f = File::create(p)?;
             f.write_all(css_str.as_bytes())?;
         } else {
-            bail!("Failed to write RON theme.")
+            bail!("Failed to write CSS theme.")
         }
 
         Ok(())
@@ -83,105 +79,126 @@ where
 }
 
 /// Trait for converting theme data into gtk4 CSS
-pub trait AsGtk4Css<C>
-where
-    C: Copy + Into<Srgba> + From<Srgba>,
-{
-    /// function for converting theme data into gtk4 CSS
-    fn as_css(&self) -> String;
+pub trait AsGtk4Css {
+    /// function for converting theme data into gtk4 CSS, each color name starts with `prefix`
+    fn as_css(&self, prefix: &str) -> String;
 }
 
-impl<C> AsGtk4Css<C> for Container<C>
+impl<C> AsGtk4Css for Container<C>
 where
-    C: Copy + Clone + fmt::Debug + Default + Into<Srgba> + From<Srgba> + fmt::Display,
+    C: Clone + fmt::Debug + Default + Into<Srgba> + From<Srgba> + Serialize + DeserializeOwned,
 {
-    fn as_css(&self) -> String {
+    fn as_css(&self, prefix: &str) -> String {
         let Self {
-            prefix,
-            container,
-            container_component,
-            container_divider,
-            container_fg,
-            ..
+            base,
+            component,
+            divider,
+            on,
         } = self;
 
-        let prefix_lower = match prefix {
-            ContainerType::Background => "background",
-            ContainerType::Primary => "primary",
-            ContainerType::Secondary => "secondary",
-        };
-        let component = widget_gtk4_css(prefix_lower, container_component);
+        let base = Hex::from(base.clone());
+        let divider = Hex::from(divider.clone());
+        let on = Hex::from(on.clone());
+        let component = component.as_css(&format!("{prefix}_component"));
 
         format!(
             r#"
-@define-color {prefix_lower}_container #{{{container}}};
-@define-color {prefix_lower}_container_divider #{{{container_divider}}};
-@define-color {prefix_lower}_container_fg #{{{container_fg}}};
-{component}
-"#
+@define-color {prefix}_container {base};
+@define-color {prefix}_container_divider {divider};
+@define-color {prefix}_container_fg {on};
+{component}"#
         )
     }
 }
 
-impl<C> AsGtk4Css<C> for Accent<C>
+impl<C> AsGtk4Css for Component<C>
 where
     C: Clone + fmt::Debug + Default + Into<Srgba> + From<Srgba> + Serialize + DeserializeOwned,
 {
-    fn as_css(&self) -> String {
-        let Accent {
-            accent,
-            accent_fg,
-            accent_nav_handle_fg,
-            suggested,
+    fn as_css(&self, prefix: &str) -> String {
+        let Self {
+            base,
+            hover,
+            pressed,
+            selected,
+            selected_text,
+            focus,
+            divider,
+            on,
+            disabled,
+            on_disabled,
         } = self;
-        let suggested = widget_gtk4_css("suggested", suggested);
+
+        let base = Hex::from(base.clone());
+        let hover = Hex::from(hover.clone());
+        let pressed = Hex::from(pressed.clone());
+        let selected = Hex::from(selected.clone());
+        let selected_text = Hex::from(selected_text.clone());
+        let focus = Hex::from(focus.clone());
+        let divider = Hex::from(divider.clone());
+        let on = Hex::from(on.clone());
+        let disabled = Hex::from(disabled.clone());
+        let on_disabled = Hex::from(on_disabled.clone());
 
         format!(
             r#"
-@define-color accent #{{{accent}}};
-@define-color accent_fg #{{{accent_fg}}};
-@define-color accent_nav_handle_fg #{{{accent_nav_handle_fg}}};
-{suggested}
+@define-color {prefix}_base {base};
+@define-color {prefix}_hover {hover};
+@define-color {prefix}_pressed {pressed};
+@define-color {prefix}_selected {selected};
+@define-color {prefix}_selected_fg {selected_text};
+@define-color {prefix}_focus {focus};
+@define-color {prefix}_divider {divider};
+@define-color {prefix}_fg {on};
+@define-color {prefix}_disabled {disabled};
+@define-color {prefix}_disabled_fg {on_disabled};
 "#
         )
     }
 }
 
-impl<C> AsGtk4Css<C> for Destructive<C>
-where
-    C: Clone + fmt::Debug + Default + Into<Srgba> + From<Srgba> + Serialize + DeserializeOwned,
-{
-    fn as_css(&self) -> String {
-        let Destructive { destructive } = &self;
-        widget_gtk4_css("destructive", destructive)
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_color_is_defined() {
+        for theme in [Theme::dark_default(), Theme::light_default()] {
+            let css = theme.as_css();
+            for container in ["background", "primary", "secondary"] {
+                for name in ["container", "container_divider", "container_fg"] {
+                    assert!(css.contains(&format!("@define-color {container}_{name} #")));
+                }
+                assert!(css.contains(&format!("@define-color {container}_component_base #")));
+            }
+            for component in [
+                "accent",
+                "success",
+                "destructive",
+                "warning",
+                "background_component",
+                "primary_component",
+                "secondary_component",
+            ] {
+                for name in [
+                    "base",
+                    "hover",
+                    "pressed",
+                    "selected",
+                    "selected_fg",
+                    "focus",
+                    "divider",
+                    "fg",
+                    "disabled",
+                    "disabled_fg",
+                ] {
+                    assert!(
+                        css.contains(&format!("@define-color {component}_{name} #")),
+                        "{component}_{name} is missing"
+                    );
+                }
+            }
+            assert_eq!(css.matches("@define-color").count(), 3 * 3 + 7 * 10);
+        }
     }
 }
-
-fn widget_gtk4_css<C: fmt::Display>(
-    prefix: &str,
-    Widget {
-        base,
-        hover,
-        pressed,
-        focused,
-        divider,
-        text,
-        text_opacity_80,
-        disabled,
-        disabled_fg,
-    }: &Widget<C>,
-) -> String {
-    format!(
-        r#"
-@define-color {prefix}_widget_base #{{{base}}};
-@define-color {prefix}_widget_hover #{{{hover}}};
-@define-color {prefix}_widget_pressed #{{{pressed}}};
-@define-color {prefix}_widget_focused #{{{focused}}};
-@define-color {prefix}_widget_divider #{{{divider}}};
-@define-color {prefix}_widget_fg #{{{text}}};
-@define-color {prefix}_widget_fg_opacity_80 #{{{text_opacity_80}}};
-@define-color {prefix}_widget_disabled #{{{disabled}}};
-@define-color {prefix}_widget_disabled_fg #{{{disabled_fg}}};
-"#
-    )
-}
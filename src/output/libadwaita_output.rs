@@ -0,0 +1,169 @@
+use super::write_user_config;
+use crate::{util::ensure_contrast, Config, Hex, Theme};
+use anyhow::Result;
+use palette::Srgba;
+use serde::{de::DeserializeOwned, Serialize};
+use std::{fmt, path::PathBuf};
+
+/// name of the gtk4 user config directory
+pub const GTK4_CONFIG_DIR: &str = "gtk-4.0";
+
+/// contrast ratio of the standalone accent and semantic colors against the window background
+const STANDALONE_CONTRAST: f32 = 4.5;
+
+/// Trait for outputting the Theme as libadwaita named colors
+pub trait LibadwaitaOutput {
+    /// turn the theme into css which overrides the libadwaita named colors
+    fn as_adwaita_css(&self) -> String;
+    /// write the css to `gtk.css` in the gtk4 user config directory
+    fn write_adwaita(&self) -> Result<PathBuf>;
+}
+
+impl<C> LibadwaitaOutput for Theme<C>
+where
+    C: Clone + fmt::Debug + Default + Into<Srgba> + From<Srgba> + Serialize + DeserializeOwned,
+{
+    fn as_adwaita_css(&self) -> String {
+        let hex = |c: &C| Hex::from(c.clone());
+        let Self {
+            background,
+            primary,
+            secondary,
+            accent,
+            success,
+            destructive,
+            warning,
+            ..
+        } = self;
+
+        // the standalone colors are drawn directly on the window and view backgrounds, like text
+        let standalone = |c: &C| {
+            Hex::from(ensure_contrast(
+                c.clone(),
+                background.base.clone(),
+                STANDALONE_CONTRAST,
+            ))
+        };
+
+        let (shade, scrollbar_outline) = if self.is_dark {
+            ("rgba(0, 0, 0, 0.36)", "rgba(0, 0, 0, 0.5)")
+        } else {
+            ("rgba(0, 0, 0, 0.07)", "white")
+        };
+
+        let colors = [
+            ("accent_color", standalone(&accent.base)),
+            ("accent_bg_color", hex(&accent.base)),
+            ("accent_fg_color", hex(&accent.on)),
+            ("destructive_color", standalone(&destructive.base)),
+            ("destructive_bg_color", hex(&destructive.base)),
+            ("destructive_fg_color", hex(&destructive.on)),
+            ("success_color", standalone(&success.base)),
+            ("success_bg_color", hex(&success.base)),
+            ("success_fg_color", hex(&success.on)),
+            ("warning_color", standalone(&warning.base)),
+            ("warning_bg_color", hex(&warning.base)),
+            ("warning_fg_color", hex(&warning.on)),
+            ("error_color", standalone(&destructive.base)),
+            ("error_bg_color", hex(&destructive.base)),
+            ("error_fg_color", hex(&destructive.on)),
+            ("window_bg_color", hex(&background.base)),
+            ("window_fg_color", hex(&background.on)),
+            ("view_bg_color", hex(&primary.base)),
+            ("view_fg_color", hex(&primary.on)),
+            ("headerbar_bg_color", hex(&background.base)),
+            ("headerbar_fg_color", hex(&background.on)),
+            ("headerbar_border_color", hex(&background.divider)),
+            ("headerbar_backdrop_color", hex(&background.base)),
+            ("headerbar_shade_color", hex(&background.divider)),
+            ("sidebar_bg_color", hex(&background.base)),
+            ("sidebar_fg_color", hex(&background.on)),
+            ("sidebar_backdrop_color", hex(&background.base)),
+            ("sidebar_shade_color", hex(&background.divider)),
+            ("card_bg_color", hex(&secondary.base)),
+            ("card_fg_color", hex(&secondary.on)),
+            ("card_shade_color", hex(&secondary.divider)),
+            ("dialog_bg_color", hex(&primary.base)),
+            ("dialog_fg_color", hex(&primary.on)),
+            ("popover_bg_color", hex(&secondary.base)),
+            ("popover_fg_color", hex(&secondary.on)),
+            ("thumbnail_bg_color", hex(&secondary.base)),
+            ("thumbnail_fg_color", hex(&secondary.on)),
+        ];
+
+        let mut css = String::new();
+        for (name, color) in colors {
+            css.push_str(&format!("@define-color {name} {color};\n"));
+        }
+        css.push_str(&format!("@define-color shade_color {shade};\n"));
+        css.push_str(&format!(
+            "@define-color scrollbar_outline_color {scrollbar_outline};\n"
+        ));
+        css
+    }
+
+    fn write_adwaita(&self) -> Result<PathBuf> {
+        write_user_config(GTK4_CONFIG_DIR, "gtk.css", &self.as_adwaita_css())
+    }
+}
+
+/// write `gtk.css` from the active theme of the config, in its high contrast variant if enabled
+/// GTK 4 only loads `gtk.css` from the user config directory, so this has to be called again when the active theme changes.
+pub fn write_adwaita_active(config: &Config) -> Result<PathBuf> {
+    config.get_active()?.write_adwaita()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::contrast_ratio;
+
+    /// the color defined as `name` in the css
+    fn color(css: &str, name: &str) -> Srgba {
+        let line = css
+            .lines()
+            .find(|l| l.starts_with(&format!("@define-color {name} ")))
+            .unwrap_or_else(|| panic!("{name} is not defined"));
+        let hex = line
+            .trim_end_matches(';')
+            .rsplit('#')
+            .next()
+            .expect("hex color");
+        let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).unwrap();
+        Srgba::new(channel(0), channel(2), channel(4), channel(6)).into_format()
+    }
+
+    #[test]
+    fn standalone_colors_are_readable_on_the_window() {
+        for theme in [Theme::dark_default(), Theme::light_default()] {
+            let css = theme.as_adwaita_css();
+            let window = color(&css, "window_bg_color");
+            for name in [
+                "accent_color",
+                "destructive_color",
+                "success_color",
+                "warning_color",
+                "error_color",
+            ] {
+                let ratio = contrast_ratio(color(&css, name), window);
+                assert!(
+                    ratio >= STANDALONE_CONTRAST - 0.01,
+                    "{name} of {} has a contrast of {ratio}",
+                    theme.name
+                );
+            }
+            for name in [
+                "accent_bg_color",
+                "accent_fg_color",
+                "view_bg_color",
+                "view_fg_color",
+                "headerbar_bg_color",
+                "card_bg_color",
+                "popover_bg_color",
+            ] {
+                color(&css, name);
+            }
+            assert!(css.contains("@define-color shade_color "));
+        }
+    }
+}
@@ -3,6 +3,80 @@
 pub mod gtk4_output;
 #[cfg(feature = "gtk4-theme")]
 pub use gtk4_output::*;
+#[cfg(feature = "libadwaita-theme")]
+/// Module for outputting the Cosmic theme as libadwaita named colors
+pub mod libadwaita_output;
+#[cfg(feature = "libadwaita-theme")]
+pub use libadwaita_output::*;
+#[cfg(feature = "gtk3-theme")]
+/// Module for outputting the Cosmic theme as gtk3 named colors
+pub mod gtk3_output;
+#[cfg(feature = "gtk3-theme")]
+pub use gtk3_output::*;
+#[cfg(feature = "qt-theme")]
+/// Module for outputting the Cosmic theme as KDE and qt5ct / qt6ct color schemes
+pub mod qt_output;
+#[cfg(feature = "qt-theme")]
+pub use qt_output::*;
+#[cfg(feature = "terminal-theme")]
+/// Module for generating terminal color schemes from the Cosmic theme
+pub mod terminal_output;
+#[cfg(feature = "terminal-theme")]
+pub use terminal_output::*;
+#[cfg(feature = "css-variables")]
+/// Module for outputting the Cosmic theme as CSS custom properties and SCSS variables
+pub mod css_variables_output;
+#[cfg(feature = "css-variables")]
+pub use css_variables_output::*;
 
 #[cfg(feature = "ron-serialization")]
 pub use ron::*;
+
+/// write a file to a directory in the user config directory, creating the directory if needed
+#[cfg(any(
+    feature = "libadwaita-theme",
+    feature = "gtk3-theme",
+    feature = "qt-theme"
+))]
+pub(crate) fn write_user_config(
+    dir: &str,
+    name: &str,
+    contents: &str,
+) -> anyhow::Result<std::path::PathBuf> {
+    use anyhow::Context;
+
+    let base_dirs = directories::BaseDirs::new().context("Failed to get base directories.")?;
+    write_file(&base_dirs.config_dir().join(dir), name, contents)
+}
+
+/// write a file to a directory in the user data directory, creating the directory if needed
+#[cfg(feature = "qt-theme")]
+pub(crate) fn write_user_data(
+    dir: &str,
+    name: &str,
+    contents: &str,
+) -> anyhow::Result<std::path::PathBuf> {
+    use anyhow::Context;
+
+    let base_dirs = directories::BaseDirs::new().context("Failed to get base directories.")?;
+    write_file(&base_dirs.data_dir().join(dir), name, contents)
+}
+
+#[cfg(any(
+    feature = "libadwaita-theme",
+    feature = "gtk3-theme",
+    feature = "qt-theme"
+))]
+fn write_file(
+    dir: &std::path::Path,
+    name: &str,
+    contents: &str,
+) -> anyhow::Result<std::path::PathBuf> {
+    use std::io::Write;
+
+    std::fs::create_dir_all(dir)?;
+    let path = dir.join(name);
+    let mut f = std::fs::File::create(&path)?;
+    f.write_all(contents.as_bytes())?;
+    Ok(path)
+}
@@ -0,0 +1,334 @@
+use super::{write_user_config, write_user_data};
+use crate::{util::over, Theme};
+use anyhow::Result;
+use palette::Srgba;
+use serde::{de::DeserializeOwned, Serialize};
+use std::{fmt, path::PathBuf};
+
+/// name of the KDE color scheme directory in the user data directory
+pub const KDE_COLOR_SCHEME_DIR: &str = "color-schemes";
+/// name of the qt5ct palette directory in the user config directory
+pub const QT5CT_COLORS_DIR: &str = "qt5ct/colors";
+/// name of the qt6ct palette directory in the user config directory
+pub const QT6CT_COLORS_DIR: &str = "qt6ct/colors";
+
+/// Trait for outputting the Theme as Qt color schemes
+pub trait QtOutput {
+    /// turn the theme into a KDE `.colors` color scheme
+    /// KDE derives the inactive and disabled colors of every group from the `ColorEffects` sections,
+    /// disabled colors are tinted toward the disabled component color and inactive colors are left as is,
+    /// as COSMIC themes have no colors for unfocused windows
+    fn as_kde_colors(&self) -> String;
+    /// turn the theme into a qt5ct / qt6ct `colors/*.conf` palette
+    /// the inactive colors are the active ones, as COSMIC themes have no colors for unfocused windows
+    fn as_qtct_palette(&self) -> String;
+    /// write the KDE color scheme to the user color scheme directory
+    fn write_kde_colors(&self) -> Result<PathBuf>;
+    /// write the palette to the qt5ct and qt6ct user color directories
+    fn write_qtct_palette(&self) -> Result<(PathBuf, PathBuf)>;
+}
+
+impl<C> QtOutput for Theme<C>
+where
+    C: Clone + fmt::Debug + Default + Into<Srgba> + From<Srgba> + Serialize + DeserializeOwned,
+{
+    fn as_kde_colors(&self) -> String {
+        let Self {
+            name,
+            background,
+            primary,
+            secondary,
+            accent,
+            success,
+            destructive,
+            warning,
+            ..
+        } = self;
+        let bg: Srgba = background.base.clone().into();
+        let kde = |c: &C| kde_color(over(c.clone(), bg));
+
+        // translucent colors are flattened over the background of their group,
+        // which is flattened over the container it is drawn on
+        let group = |group: &str,
+                     backdrop: Srgba,
+                     base: &C,
+                     alternate: &C,
+                     on: &C,
+                     inactive: &C,
+                     link: &C| {
+            let base: Srgba = over(base.clone(), backdrop);
+            let kde = |c: &C| kde_color(over(c.clone(), base));
+            format!(
+                "[Colors:{group}]\nBackgroundAlternate={}\nBackgroundNormal={}\nDecorationFocus={}\nDecorationHover={}\nForegroundActive={}\nForegroundInactive={}\nForegroundLink={}\nForegroundNegative={}\nForegroundNeutral={}\nForegroundNormal={}\nForegroundPositive={}\nForegroundVisited={}\n\n",
+                kde(alternate),
+                kde_color(base),
+                kde(&accent.base),
+                kde(&accent.base),
+                kde(link),
+                kde(inactive),
+                kde(link),
+                kde(&destructive.base),
+                kde(&warning.base),
+                kde(on),
+                kde(&success.base),
+                kde(link),
+            )
+        };
+
+        let mut colors = String::new();
+        colors.push_str(&format!(
+            "[ColorEffects:Disabled]\nColor={}\nColorAmount=0.5\nColorEffect=3\nContrastAmount=0.5\nContrastEffect=0\nIntensityAmount=0\nIntensityEffect=0\n\n",
+            kde(&background.component.disabled),
+        ));
+        colors.push_str(&format!(
+            "[ColorEffects:Inactive]\nChangeSelectionColor=false\nColor={}\nColorAmount=0\nColorEffect=0\nContrastAmount=0\nContrastEffect=0\nEnable=false\nIntensityAmount=0\nIntensityEffect=0\n\n",
+            kde(&background.base),
+        ));
+        colors.push_str(&group(
+            "Button",
+            bg,
+            &background.component.base,
+            &background.component.hover,
+            &background.component.on,
+            &background.component.on_disabled,
+            &background.component.selected_text,
+        ));
+        colors.push_str(&group(
+            "Header",
+            bg,
+            &background.base,
+            &background.component.base,
+            &background.on,
+            &background.component.on_disabled,
+            &background.component.selected_text,
+        ));
+        colors.push_str(&group(
+            "Selection",
+            over(primary.base.clone(), bg),
+            &primary.component.selected,
+            &primary.component.hover,
+            &primary.component.selected_text,
+            &primary.component.on_disabled,
+            &primary.component.selected_text,
+        ));
+        colors.push_str(&group(
+            "Tooltip",
+            bg,
+            &secondary.base,
+            &secondary.component.base,
+            &secondary.on,
+            &secondary.component.on_disabled,
+            &secondary.component.selected_text,
+        ));
+        colors.push_str(&group(
+            "View",
+            bg,
+            &primary.base,
+            &primary.component.base,
+            &primary.on,
+            &primary.component.on_disabled,
+            &primary.component.selected_text,
+        ));
+        colors.push_str(&group(
+            "Window",
+            bg,
+            &background.base,
+            &background.component.base,
+            &background.on,
+            &background.component.on_disabled,
+            &background.component.selected_text,
+        ));
+        colors.push_str(&format!(
+            "[General]\nColorScheme={name}\nName={name}\nshadeSortColumn=true\n\n[KDE]\ncontrast=4\n\n"
+        ));
+        colors.push_str(&format!(
+            "[WM]\nactiveBackground={}\nactiveBlend={}\nactiveForeground={}\ninactiveBackground={}\ninactiveBlend={}\ninactiveForeground={}\n",
+            kde(&background.base),
+            kde(&background.on),
+            kde(&background.on),
+            kde(&background.base),
+            kde(&background.component.on_disabled),
+            kde(&background.component.on_disabled),
+        ));
+        colors
+    }
+
+    fn as_qtct_palette(&self) -> String {
+        let Self {
+            background,
+            primary,
+            secondary,
+            accent,
+            ..
+        } = self;
+        // translucent colors are flattened over the container they are drawn on
+        let bg: Srgba = background.base.clone().into();
+        let qt = |c: &C| qtct_color(over(c.clone(), bg));
+        let primary_bg = over(primary.base.clone(), bg);
+        let on_primary = |c: &C| qtct_color(over(c.clone(), primary_bg));
+        let primary_component_bg = over(primary.component.base.clone(), primary_bg);
+        let on_primary_component = |c: &C| qtct_color(over(c.clone(), primary_component_bg));
+        let on_secondary = |c: &C| qtct_color(over(c.clone(), over(secondary.base.clone(), bg)));
+        // selected items in views are drawn with the selected state of the view components
+        let selected = over(primary.component.selected.clone(), primary_bg);
+        let on_selected = |c: &C| qtct_color(over(c.clone(), selected));
+        let shadow = qtct_color(Srgba::new(0.0, 0.0, 0.0, 1.0));
+
+        // roles in the order of QPalette::ColorRole
+        let active = [
+            qt(&background.on),
+            qt(&background.component.base),
+            qt(&secondary.base),
+            qt(&primary.base),
+            qt(&background.divider),
+            qt(&background.component.pressed),
+            on_primary(&primary.on),
+            qt(&accent.on),
+            qt(&background.component.on),
+            qt(&primary.base),
+            qt(&background.base),
+            shadow.clone(),
+            qtct_color(selected),
+            on_selected(&primary.component.selected_text),
+            qt(&background.component.selected_text),
+            qt(&accent.pressed),
+            on_primary(&primary.component.base),
+            qt(&background.base),
+            qt(&secondary.base),
+            on_secondary(&secondary.on),
+            on_primary_component(&primary.component.on_disabled),
+        ];
+        let disabled = [
+            qt(&background.component.on_disabled),
+            qt(&background.component.disabled),
+            qt(&secondary.base),
+            qt(&primary.base),
+            qt(&background.divider),
+            qt(&background.component.disabled),
+            on_primary(&primary.component.on_disabled),
+            qt(&accent.on_disabled),
+            qt(&background.component.on_disabled),
+            qt(&primary.base),
+            qt(&background.base),
+            shadow,
+            qtct_color(selected),
+            on_selected(&primary.component.on_disabled),
+            qt(&background.component.on_disabled),
+            qt(&background.component.on_disabled),
+            on_primary(&primary.component.base),
+            qt(&background.base),
+            qt(&secondary.base),
+            on_secondary(&secondary.component.on_disabled),
+            on_primary_component(&primary.component.on_disabled),
+        ];
+
+        format!(
+            "[ColorScheme]\nactive_colors={}\ndisabled_colors={}\ninactive_colors={}\n",
+            active.join(", "),
+            disabled.join(", "),
+            active.join(", "),
+        )
+    }
+
+    fn write_kde_colors(&self) -> Result<PathBuf> {
+        write_user_data(
+            KDE_COLOR_SCHEME_DIR,
+            &format!("{}.colors", self.name),
+            &self.as_kde_colors(),
+        )
+    }
+
+    fn write_qtct_palette(&self) -> Result<(PathBuf, PathBuf)> {
+        let palette = self.as_qtct_palette();
+        let name = format!("{}.conf", self.name);
+        Ok((
+            write_user_config(QT5CT_COLORS_DIR, &name, &palette)?,
+            write_user_config(QT6CT_COLORS_DIR, &name, &palette)?,
+        ))
+    }
+}
+
+/// KDE colors are opaque comma separated rgb values
+fn kde_color(c: Srgba) -> String {
+    let c: palette::rgb::Rgba<palette::encoding::Srgb, u8> = c.into_format();
+    format!("{},{},{}", c.red, c.green, c.blue)
+}
+
+/// qt5ct and qt6ct colors are #aarrggbb
+fn qtct_color(c: Srgba) -> String {
+    let c: palette::rgb::Rgba<palette::encoding::Srgb, u8> = c.into_format();
+    format!("#{:02x}{:02x}{:02x}{:02x}", c.alpha, c.red, c.green, c.blue)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// the colors of a qtct palette line
+    fn qtct_line<'a>(palette: &'a str, key: &str) -> Vec<&'a str> {
+        palette
+            .lines()
+            .find_map(|l| l.strip_prefix(&format!("{key}=")))
+            .unwrap_or_else(|| panic!("{key} is missing"))
+            .split(", ")
+            .collect()
+    }
+
+    #[test]
+    fn kde_colors_have_every_group() {
+        for theme in [Theme::dark_default(), Theme::light_default()] {
+            let colors = theme.as_kde_colors();
+            for section in [
+                "[ColorEffects:Disabled]",
+                "[ColorEffects:Inactive]",
+                "[Colors:Button]",
+                "[Colors:Header]",
+                "[Colors:Selection]",
+                "[Colors:Tooltip]",
+                "[Colors:View]",
+                "[Colors:Window]",
+                "[General]",
+                "[KDE]",
+                "[WM]",
+            ] {
+                assert!(colors.contains(section), "{section} is missing");
+            }
+            assert_eq!(colors.matches("BackgroundNormal=").count(), 6);
+
+            let bg: Srgba = theme.background.base.into();
+            let view = over(theme.primary.base, bg);
+            let selection = colors
+                .split("[Colors:Selection]")
+                .nth(1)
+                .expect("selection group");
+            assert!(selection.contains(&format!(
+                "BackgroundNormal={}\n",
+                kde_color(over(theme.primary.component.selected, view))
+            )));
+        }
+    }
+
+    #[test]
+    fn qtct_palette_has_every_role() {
+        for theme in [Theme::dark_default(), Theme::light_default()] {
+            let palette = theme.as_qtct_palette();
+            for key in ["active_colors", "disabled_colors", "inactive_colors"] {
+                let colors = qtct_line(&palette, key);
+                assert_eq!(colors.len(), 21, "{key} has {} roles", colors.len());
+                assert!(colors.iter().all(|c| c.len() == 9 && c.starts_with("#ff")));
+            }
+
+            let bg: Srgba = theme.background.base.into();
+            let view = over(theme.primary.base, bg);
+            let highlight = over(theme.primary.component.selected, view);
+            assert_eq!(
+                qtct_line(&palette, "active_colors")[12],
+                qtct_color(highlight)
+            );
+            assert_eq!(
+                qtct_line(&palette, "active_colors")[13],
+                qtct_color(over(theme.primary.component.selected_text, highlight))
+            );
+        }
+    }
+}
@@ -0,0 +1,342 @@
+use crate::{
+    util::{ensure_contrast, over},
+    Theme,
+};
+use palette::{FromColor, IntoColor, Lch, Srgb, Srgba};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::fmt;
+
+/// default minimum contrast ratio of the ANSI colors against the terminal background
+pub const DEFAULT_TERMINAL_CONTRAST: f32 = 4.5;
+
+const ANSI_NAMES: [&str; 8] = [
+    "black", "red", "green", "yellow", "blue", "magenta", "cyan", "white",
+];
+
+/// 16 color ANSI terminal color scheme derived from a theme
+#[derive(Clone, Debug, PartialEq)]
+pub struct TerminalScheme {
+    /// name of the color scheme
+    pub name: String,
+    /// default text color
+    pub foreground: Srgba,
+    /// default background color
+    pub background: Srgba,
+    /// cursor color
+    pub cursor: Srgba,
+    /// color of text under the cursor
+    pub cursor_text: Srgba,
+    /// background color of selected text
+    pub selection_background: Srgba,
+    /// color of selected text
+    pub selection_foreground: Srgba,
+    /// ANSI colors 0 - 7
+    pub normal: [Srgba; 8],
+    /// ANSI colors 8 - 15
+    pub bright: [Srgba; 8],
+}
+
+impl TerminalScheme {
+    /// derive a terminal color scheme from the theme and its palette
+    pub fn from_theme<C>(theme: &Theme<C>) -> Self
+    where
+        C: Clone + fmt::Debug + Default + Into<Srgba> + From<Srgba> + Serialize + DeserializeOwned,
+    {
+        Self::from_theme_with_contrast(theme, DEFAULT_TERMINAL_CONTRAST)
+    }
+
+    /// derive a terminal color scheme, raising the contrast of the colored ANSI colors against the background to at least `min_contrast`
+    pub fn from_theme_with_contrast<C>(theme: &Theme<C>, min_contrast: f32) -> Self
+    where
+        C: Clone + fmt::Debug + Default + Into<Srgba> + From<Srgba> + Serialize + DeserializeOwned,
+    {
+        let p = &theme.palette;
+        let c = |c: &C| -> Srgba { c.clone().into() };
+        let background = c(&theme.background.base);
+        let foreground = over(theme.background.on.clone(), background);
+
+        // black is always the darker and white the lighter end, regardless of the theme
+        let (black, bright_black, white, bright_white) = if theme.is_dark {
+            (
+                p.neutral_3.clone(),
+                p.neutral_5.clone(),
+                p.neutral_8.clone(),
+                p.neutral_10.clone(),
+            )
+        } else {
+            (
+                p.neutral_9.clone(),
+                p.neutral_7.clone(),
+                p.neutral_3.clone(),
+                p.neutral_1.clone(),
+            )
+        };
+        // the palette's `blue` is the accent, which is cyan in the built in palettes and changes with accent presets,
+        // so ANSI blue and cyan use the fixed extended indigo and blue instead
+        let colors = [
+            c(&p.red),
+            c(&p.green),
+            c(&p.yellow),
+            c(&p.ext_indigo),
+            c(&p.ext_purple),
+            c(&p.ext_blue),
+        ];
+
+        let mut normal = [c(&black); 8];
+        let mut bright = [c(&bright_black); 8];
+        normal[7] = c(&white);
+        bright[7] = c(&bright_white);
+        for (i, color) in colors.into_iter().enumerate() {
+            normal[i + 1] = ensure_contrast(color, background, min_contrast);
+            bright[i + 1] = ensure_contrast(lighten(color, 10.0), background, min_contrast);
+        }
+
+        let accent = over(theme.accent.base.clone(), background);
+        let on_accent = over(theme.accent.on.clone(), accent);
+
+        Self {
+            name: theme.name.clone(),
+            foreground,
+            background,
+            cursor: accent,
+            cursor_text: on_accent,
+            selection_background: accent,
+            selection_foreground: on_accent,
+            normal,
+            bright,
+        }
+    }
+
+    /// ANSI colors 0 - 15
+    pub fn ansi(&self) -> [Srgba; 16] {
+        let mut ansi = [self.background; 16];
+        ansi[..8].copy_from_slice(&self.normal);
+        ansi[8..].copy_from_slice(&self.bright);
+        ansi
+    }
+
+    /// Alacritty TOML color configuration
+    pub fn as_alacritty(&self) -> String {
+        let mut s = format!(
+            "[colors.primary]\nbackground = \"{}\"\nforeground = \"{}\"\n\n[colors.cursor]\ncursor = \"{}\"\ntext = \"{}\"\n\n[colors.selection]\nbackground = \"{}\"\ntext = \"{}\"\n",
+            hex(self.background),
+            hex(self.foreground),
+            hex(self.cursor),
+            hex(self.cursor_text),
+            hex(self.selection_background),
+            hex(self.selection_foreground),
+        );
+        for (section, colors) in [("normal", &self.normal), ("bright", &self.bright)] {
+            s.push_str(&format!("\n[colors.{section}]\n"));
+            for (name, color) in ANSI_NAMES.iter().zip(colors) {
+                s.push_str(&format!("{name} = \"{}\"\n", hex(*color)));
+            }
+        }
+        s
+    }
+
+    /// Kitty color configuration
+    pub fn as_kitty(&self) -> String {
+        let mut s = format!(
+            "# {}\nforeground {}\nbackground {}\ncursor {}\ncursor_text_color {}\nselection_foreground {}\nselection_background {}\n",
+            self.name,
+            hex(self.foreground),
+            hex(self.background),
+            hex(self.cursor),
+            hex(self.cursor_text),
+            hex(self.selection_foreground),
+            hex(self.selection_background),
+        );
+        for (i, color) in self.ansi().iter().enumerate() {
+            s.push_str(&format!("color{i} {}\n", hex(*color)));
+        }
+        s
+    }
+
+    /// foot color configuration
+    pub fn as_foot(&self) -> String {
+        // foot colors have no leading #
+        let foot = |c: Srgba| hex(c)[1..].to_string();
+        let mut s = format!(
+            "# {}\n[cursor]\ncolor={} {}\n\n[colors]\nforeground={}\nbackground={}\nselection-foreground={}\nselection-background={}\n",
+            self.name,
+            foot(self.cursor_text),
+            foot(self.cursor),
+            foot(self.foreground),
+            foot(self.background),
+            foot(self.selection_foreground),
+            foot(self.selection_background),
+        );
+        for (i, color) in self.normal.iter().enumerate() {
+            s.push_str(&format!("regular{i}={}\n", foot(*color)));
+        }
+        for (i, color) in self.bright.iter().enumerate() {
+            s.push_str(&format!("bright{i}={}\n", foot(*color)));
+        }
+        s
+    }
+
+    /// WezTerm TOML color scheme
+    pub fn as_wezterm(&self) -> String {
+        let list = |colors: &[Srgba; 8]| {
+            colors
+                .iter()
+                .map(|c| format!("\"{}\"", hex(*c)))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        format!(
+            "[colors]\nforeground = \"{}\"\nbackground = \"{}\"\ncursor_bg = \"{}\"\ncursor_border = \"{}\"\ncursor_fg = \"{}\"\nselection_bg = \"{}\"\nselection_fg = \"{}\"\nansi = [{}]\nbrights = [{}]\n\n[metadata]\nname = \"{}\"\n",
+            hex(self.foreground),
+            hex(self.background),
+            hex(self.cursor),
+            hex(self.cursor),
+            hex(self.cursor_text),
+            hex(self.selection_background),
+            hex(self.selection_foreground),
+            list(&self.normal),
+            list(&self.bright),
+            self.name,
+        )
+    }
+
+    /// COSMIC terminal RON color scheme
+    pub fn as_cosmic_term(&self) -> anyhow::Result<String> {
+        let ansi = |colors: [Srgba; 8]| {
+            let [black, red, green, yellow, blue, magenta, cyan, white] = colors.map(hex);
+            CosmicTermAnsi {
+                black,
+                red,
+                green,
+                yellow,
+                blue,
+                magenta,
+                cyan,
+                white,
+            }
+        };
+        // dim colors are moved a third of the way towards the background
+        let dim = |c: Srgba| {
+            let mut c = c;
+            c.alpha = 0.66;
+            over(c, self.background)
+        };
+        let scheme = CosmicTermScheme {
+            name: self.name.clone(),
+            foreground: hex(self.foreground),
+            background: hex(self.background),
+            cursor: hex(self.cursor),
+            bright_foreground: hex(self.bright[7]),
+            dim_foreground: hex(dim(self.foreground)),
+            normal: ansi(self.normal),
+            bright: ansi(self.bright),
+            dim: ansi(self.normal.map(dim)),
+        };
+        Ok(ron::ser::to_string_pretty(&scheme, Default::default())?)
+    }
+}
+
+/// COSMIC terminal ANSI colors
+#[derive(Clone, Debug, Deserialize, Serialize)]
+struct CosmicTermAnsi {
+    black: String,
+    red: String,
+    green: String,
+    yellow: String,
+    blue: String,
+    magenta: String,
+    cyan: String,
+    white: String,
+}
+
+/// COSMIC terminal color scheme
+#[derive(Clone, Debug, Deserialize, Serialize)]
+struct CosmicTermScheme {
+    name: String,
+    foreground: String,
+    background: String,
+    cursor: String,
+    bright_foreground: String,
+    dim_foreground: String,
+    normal: CosmicTermAnsi,
+    bright: CosmicTermAnsi,
+    dim: CosmicTermAnsi,
+}
+
+fn lighten(c: Srgba, amount: f32) -> Srgba {
+    let mut lch: Lch = c.color.into_color();
+    lch.l = (lch.l + amount).min(100.0);
+    Srgb::from_color(lch).into()
+}
+
+/// opaque #rrggbb
+fn hex(c: Srgba) -> String {
+    let c: palette::rgb::Rgb<palette::encoding::Srgb, u8> = c.color.into_format();
+    format!("#{:02x}{:02x}{:02x}", c.red, c.green, c.blue)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::contrast_ratio;
+
+    #[test]
+    fn ansi_colors_are_readable() {
+        for theme in [Theme::dark_default(), Theme::light_default()] {
+            let scheme = TerminalScheme::from_theme(&theme);
+            for color in scheme.normal[1..7].iter().chain(&scheme.bright[1..7]) {
+                let ratio = contrast_ratio(*color, scheme.background);
+                assert!(
+                    ratio >= DEFAULT_TERMINAL_CONTRAST - 0.01,
+                    "{} has a contrast of {ratio}",
+                    hex(*color)
+                );
+            }
+            assert_eq!(scheme.ansi()[..8], scheme.normal);
+            assert_eq!(scheme.ansi()[8..], scheme.bright);
+        }
+    }
+
+    #[test]
+    fn every_format_has_every_color() {
+        for theme in [Theme::dark_default(), Theme::light_default()] {
+            let scheme = TerminalScheme::from_theme(&theme);
+
+            let alacritty = scheme.as_alacritty();
+            for section in [
+                "[colors.primary]",
+                "[colors.cursor]",
+                "[colors.selection]",
+                "[colors.normal]",
+                "[colors.bright]",
+            ] {
+                assert!(alacritty.contains(section), "{section} is missing");
+            }
+            for name in ANSI_NAMES {
+                assert_eq!(alacritty.matches(&format!("\n{name} = \"#")).count(), 2);
+            }
+
+            let kitty = scheme.as_kitty();
+            for i in 0..16 {
+                assert!(kitty.contains(&format!("\ncolor{i} #")));
+            }
+            assert!(kitty.contains("\nselection_background #"));
+
+            let foot = scheme.as_foot();
+            for i in 0..8 {
+                assert!(foot.contains(&format!("\nregular{i}=")));
+                assert!(foot.contains(&format!("\nbright{i}=")));
+            }
+
+            let wezterm = scheme.as_wezterm();
+            for key in ["ansi = [", "brights = [", "cursor_bg = ", "selection_bg = "] {
+                assert!(wezterm.contains(key), "{key} is missing");
+            }
+
+            let cosmic_term = scheme.as_cosmic_term().unwrap();
+            let parsed: CosmicTermScheme = ron::from_str(&cosmic_term).unwrap();
+            assert_eq!(parsed.name, theme.name);
+            assert_eq!(parsed.normal.red, hex(scheme.normal[1]));
+        }
+    }
+}
@@ -0,0 +1,157 @@
+use anyhow::{Context, Result};
+use directories::ProjectDirsExt;
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    fmt,
+    fs::File,
+    io::{Read, Write},
+    path::Path,
+};
+
+/// Format used for saving and loading themes, palettes and configs
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SerializationFormat {
+    /// Rusty Object Notation
+    #[default]
+    Ron,
+    /// JSON
+    #[cfg(feature = "json-serialization")]
+    Json,
+    /// TOML
+    #[cfg(feature = "toml-serialization")]
+    Toml,
+}
+
+impl SerializationFormat {
+    /// every format enabled by the crate features
+    pub const ALL: &'static [Self] = &[
+        Self::Ron,
+        #[cfg(feature = "json-serialization")]
+        Self::Json,
+        #[cfg(feature = "toml-serialization")]
+        Self::Toml,
+    ];
+
+    /// file extension of the format
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Ron => "ron",
+            #[cfg(feature = "json-serialization")]
+            Self::Json => "json",
+            #[cfg(feature = "toml-serialization")]
+            Self::Toml => "toml",
+        }
+    }
+
+    /// choose the format from the extension of the path
+    pub fn from_path(p: &Path) -> Result<Self> {
+        match p.extension().and_then(|e| e.to_str()) {
+            Some("json") => {
+                #[cfg(feature = "json-serialization")]
+                return Ok(Self::Json);
+                #[cfg(not(feature = "json-serialization"))]
+                anyhow::bail!("JSON support requires the json-serialization feature")
+            }
+            Some("toml") => {
+                #[cfg(feature = "toml-serialization")]
+                return Ok(Self::Toml);
+                #[cfg(not(feature = "toml-serialization"))]
+                anyhow::bail!("TOML support requires the toml-serialization feature")
+            }
+            Some("ron") => Ok(Self::Ron),
+            Some(e) => anyhow::bail!("unsupported file extension \"{e}\" of {}", p.display()),
+            None => anyhow::bail!("{} has no file extension", p.display()),
+        }
+    }
+
+    /// serialize a value in this format
+    pub fn serialize<T: Serialize>(&self, value: &T) -> Result<String> {
+        match self {
+            Self::Ron => Ok(ron::ser::to_string_pretty(value, Default::default())?),
+            #[cfg(feature = "json-serialization")]
+            Self::Json => Ok(serde_json::to_string_pretty(value)?),
+            #[cfg(feature = "toml-serialization")]
+            Self::Toml => Ok(toml::to_string_pretty(value)?),
+        }
+    }
+
+    /// deserialize a value in this format
+    pub fn deserialize<T: DeserializeOwned>(&self, s: &str) -> Result<T> {
+        match self {
+            Self::Ron => Ok(ron::from_str(s)?),
+            #[cfg(feature = "json-serialization")]
+            Self::Json => Ok(serde_json::from_str(s)?),
+            #[cfg(feature = "toml-serialization")]
+            Self::Toml => Ok(toml::from_str(s)?),
+        }
+    }
+}
+
+impl fmt::Display for SerializationFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Ron => write!(f, "RON"),
+            #[cfg(feature = "json-serialization")]
+            Self::Json => write!(f, "JSON"),
+            #[cfg(feature = "toml-serialization")]
+            Self::Toml => write!(f, "TOML"),
+        }
+    }
+}
+
+/// save a value to a path, in the format matching its extension
+pub(crate) fn save_to_path<T: Serialize>(value: &T, p: &Path) -> Result<()> {
+    let format = SerializationFormat::from_path(p)?;
+    let s = format.serialize(value)?;
+    let mut f = File::create(p)?;
+    f.write_all(s.as_bytes())?;
+    Ok(())
+}
+
+/// load a value from a path, in the format matching its extension
+pub(crate) fn load_from_path<T: DeserializeOwned>(p: &Path) -> Result<T> {
+    let format = SerializationFormat::from_path(p)?;
+    let mut f = File::open(p)?;
+    let mut s = String::new();
+    f.read_to_string(&mut s)?;
+    format
+        .deserialize(&s)
+        .with_context(|| format!("Failed to parse {format} from {}", p.display()))
+}
+
+/// remove the files named `name` in every format except `format`
+/// loading by name tries the formats in order, so a stale file in another format would shadow the saved one
+pub(crate) fn remove_other_formats(
+    dirs: &directories::ProjectDirs,
+    name: &str,
+    format: SerializationFormat,
+) -> Result<()> {
+    for other in SerializationFormat::ALL.iter().filter(|f| **f != format) {
+        if let Some(path) = dirs.find_config_file(format!("{name}.{}", other.extension())) {
+            std::fs::remove_file(path)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_is_chosen_by_extension() {
+        assert_eq!(
+            SerializationFormat::from_path(Path::new("theme.ron")).unwrap(),
+            SerializationFormat::Ron
+        );
+        for format in SerializationFormat::ALL {
+            let path = format!("theme.{}", format.extension());
+            assert_eq!(
+                SerializationFormat::from_path(Path::new(&path)).unwrap(),
+                *format
+            );
+        }
+        assert!(SerializationFormat::from_path(Path::new("theme.yaml")).is_err());
+        assert!(SerializationFormat::from_path(Path::new("theme")).is_err());
+    }
+}
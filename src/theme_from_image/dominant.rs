@@ -0,0 +1,174 @@
+use crate::util::{Oklab, Oklch};
+use palette::Srgba;
+
+/// chroma from which a color counts as fully vivid, in Oklch
+const VIVID_CHROMA: f32 = 0.15;
+
+/// How the colors extracted from an image are ranked
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum ColorWeighting {
+    /// by share of pixels, most common first
+    #[default]
+    Population,
+    /// the background candidate first, then the accent candidates
+    /// by chroma, saliency and share of pixels together
+    Perceptual,
+}
+
+/// A color extracted from an image with its score
+#[derive(Copy, Clone, Debug)]
+pub struct ScoredColor {
+    /// the color
+    pub color: Srgba,
+    /// share of sampled pixels, from 0 to 1
+    pub population: f32,
+    /// Oklch chroma
+    pub chroma: f32,
+    /// how much the color stands out from the rest of the image, from 0 to 1
+    pub saliency: f32,
+    /// score of the color for its role, from 0 to 1
+    pub score: f32,
+}
+
+/// The dominant colors of an image
+#[derive(Clone, Debug)]
+pub struct DominantColors {
+    /// large and calm color, best suited for the background
+    pub background: ScoredColor,
+    /// remaining colors, most vivid and distinct first
+    pub accents: Vec<ScoredColor>,
+}
+
+impl DominantColors {
+    /// score clustered colors with their share of pixels
+    ///
+    /// The background is the color with the largest share, penalized by its chroma.
+    /// Accents are scored by chroma times saliency times the square root of their share,
+    /// so a small vivid area can beat a large dull one.
+    /// Saliency is the share weighted Oklab distance to every other color,
+    /// relative to the most salient color.
+    pub(crate) fn from_clusters(clusters: &[(Srgba, f32)]) -> Option<Self> {
+        let labs: Vec<Oklab> = clusters.iter().map(|(c, _)| c.color.into()).collect();
+        let saliency: Vec<f32> = labs
+            .iter()
+            .map(|a| {
+                labs.iter()
+                    .zip(clusters)
+                    .map(|(b, (_, population))| {
+                        population
+                            * ((a.l - b.l).powi(2) + (a.a - b.a).powi(2) + (a.b - b.b).powi(2))
+                                .sqrt()
+                    })
+                    .sum()
+            })
+            .collect();
+        let max_saliency = saliency.iter().copied().fold(0.0, f32::max);
+
+        let mut colors: Vec<ScoredColor> = clusters
+            .iter()
+            .zip(saliency)
+            .map(|(&(color, population), saliency)| ScoredColor {
+                color,
+                population,
+                chroma: Oklch::from(color.color).chroma,
+                saliency: if max_saliency > 0.0 {
+                    saliency / max_saliency
+                } else {
+                    0.0
+                },
+                score: 0.0,
+            })
+            .collect();
+        let vividness = |c: &ScoredColor| (c.chroma / VIVID_CHROMA).min(1.0);
+
+        let (bg_i, bg_score) = colors
+            .iter()
+            .map(|c| c.population * (1.0 - 0.5 * vividness(c)))
+            .enumerate()
+            .max_by(|a, b| a.1.total_cmp(&b.1))?;
+        let mut background = colors.remove(bg_i);
+        background.score = bg_score;
+
+        for c in &mut colors {
+            c.score = vividness(c) * c.saliency * c.population.sqrt();
+        }
+        colors.sort_by(|a, b| b.score.total_cmp(&a.score));
+        Some(Self {
+            background,
+            accents: colors,
+        })
+    }
+
+    /// the background candidate followed by the accent candidates
+    pub fn colors(&self) -> Vec<Srgba> {
+        std::iter::once(self.background.color)
+            .chain(self.accents.iter().map(|c| c.color))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::theme_from_image::{theme_from_rgba_image, ImageColorOptions};
+    use image::{Rgba, RgbaImage};
+
+    fn rgb(red: u8, green: u8, blue: u8) -> Srgba {
+        Srgba::new(red, green, blue, 255).into_format()
+    }
+
+    #[test]
+    fn vivid_minority_outranks_dull_majority() {
+        let beige = rgb(200, 190, 170);
+        let brown = rgb(120, 110, 100);
+        let magenta = rgb(220, 30, 180);
+        let colors =
+            DominantColors::from_clusters(&[(beige, 0.6), (brown, 0.3), (magenta, 0.1)]).unwrap();
+        assert_eq!(colors.background.color, beige);
+        assert_eq!(colors.accents[0].color, magenta);
+        assert!(colors.accents[0].score > colors.accents[1].score);
+        assert_eq!(colors.colors(), vec![beige, magenta, brown]);
+    }
+
+    #[test]
+    fn background_prefers_calm_colors() {
+        let red = rgb(230, 20, 20);
+        let gray = rgb(90, 90, 90);
+        let colors =
+            DominantColors::from_clusters(&[(red, 0.45), (gray, 0.4), (rgb(0, 0, 0), 0.15)])
+                .unwrap();
+        assert_eq!(colors.background.color, gray);
+        assert_eq!(colors.accents[0].color, red);
+        assert!(DominantColors::from_clusters(&[]).is_none());
+    }
+
+    /// colors clustered from pixels are compared as 8 bit colors, as they went through Lab
+    fn rgb8(c: Srgba) -> [u8; 3] {
+        let c: palette::rgb::Rgb<palette::encoding::Srgb, u8> = c.color.into_format();
+        [c.red, c.green, c.blue]
+    }
+
+    #[test]
+    fn perceptual_weighting_ranks_vivid_accents_first() {
+        // a dull image with a small vivid spot
+        let img = RgbaImage::from_fn(20, 20, |x, y| match (x, y) {
+            (0..=3, 0..=3) => Rgba([30, 200, 60, 255]),
+            (_, 0..=7) => Rgba([150, 140, 130, 255]),
+            _ => Rgba([190, 180, 165, 255]),
+        });
+        let options = ImageColorOptions {
+            clusters: 3,
+            ..Default::default()
+        };
+        let population = theme_from_rgba_image(&img, &options).unwrap();
+        assert_eq!(rgb8(population[2]), [30, 200, 60]);
+
+        let options = ImageColorOptions {
+            weighting: ColorWeighting::Perceptual,
+            ..options
+        };
+        let perceptual = theme_from_rgba_image(&img, &options).unwrap();
+        assert_eq!(rgb8(perceptual[0]), [190, 180, 165]);
+        assert_eq!(rgb8(perceptual[1]), [30, 200, 60]);
+    }
+}
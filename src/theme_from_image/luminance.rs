@@ -0,0 +1,137 @@
+use super::DominantColors;
+use crate::util::{is_dark, luminance, ContrastModel};
+use palette::Srgba;
+
+/// Whether a dark or light theme is derived from an image
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum VariantPreference {
+    /// follow the luminance of the image
+    #[default]
+    Auto,
+    /// always dark
+    Dark,
+    /// always light
+    Light,
+}
+
+/// Relative luminance of an image, weighted by the share of pixels of each color
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct LuminanceAnalysis {
+    /// mean relative luminance, from 0 to 1
+    pub mean: f32,
+    /// median relative luminance, from 0 to 1
+    pub median: f32,
+}
+
+impl LuminanceAnalysis {
+    /// analyze colors with their share of pixels
+    /// returns None if there are no colors or every share is 0
+    pub fn from_weighted_colors(colors: &[(Srgba, f32)]) -> Option<Self> {
+        let mut weighted: Vec<(f32, f32)> = colors
+            .iter()
+            .filter(|(_, w)| *w > 0.0)
+            .map(|(c, w)| (luminance(*c), *w))
+            .collect();
+        let total: f32 = weighted.iter().map(|(_, w)| w).sum();
+        if total <= 0.0 {
+            return None;
+        }
+        let mean = weighted.iter().map(|(y, w)| y * w).sum::<f32>() / total;
+
+        weighted.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let mut cumulative = 0.0;
+        let median = weighted
+            .iter()
+            .find(|(_, w)| {
+                cumulative += w;
+                cumulative >= total / 2.0
+            })
+            .map_or(mean, |(y, _)| *y);
+        Some(Self { mean, median })
+    }
+
+    /// whether a dark theme suits the image
+    /// The median is used, so small bright areas like the sun don't flip the decision.
+    /// An image is dark when white text would have more contrast on its median gray than black text.
+    pub fn recommends_dark(&self) -> bool {
+        is_dark(ContrastModel::Wcag2.gray(self.median))
+    }
+
+    /// whether a dark theme is derived, following the preference unless it is automatic
+    pub fn is_dark(&self, preference: VariantPreference) -> bool {
+        match preference {
+            VariantPreference::Auto => self.recommends_dark(),
+            VariantPreference::Dark => true,
+            VariantPreference::Light => false,
+        }
+    }
+}
+
+impl DominantColors {
+    /// luminance of the image, from the extracted colors and their share of pixels
+    pub fn luminance(&self) -> Option<LuminanceAnalysis> {
+        let colors: Vec<(Srgba, f32)> = std::iter::once(&self.background)
+            .chain(&self.accents)
+            .map(|c| (c.color, c.population))
+            .collect();
+        LuminanceAnalysis::from_weighted_colors(&colors)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::theme_from_image::{extract_dominant_colors, ImageColorOptions};
+    use image::{Rgba, RgbaImage};
+
+    fn gray(v: u8) -> Srgba {
+        Srgba::new(v, v, v, 255).into_format()
+    }
+
+    /// luminance of an image with the top `dark_rows` of 10 rows in `dark` and the rest in `light`
+    fn analyze(dark: u8, light: u8, dark_rows: u32) -> LuminanceAnalysis {
+        let img = RgbaImage::from_fn(10, 10, |_, y| {
+            let v = if y < dark_rows { dark } else { light };
+            Rgba([v, v, v, 255])
+        });
+        extract_dominant_colors(&img, &ImageColorOptions::default())
+            .unwrap()
+            .luminance()
+            .unwrap()
+    }
+
+    #[test]
+    fn images_are_classified_by_their_median() {
+        let dark = analyze(20, 240, 7);
+        assert!(dark.median < 0.01);
+        assert!(dark.recommends_dark());
+
+        let light = analyze(20, 240, 3);
+        assert!(light.median > 0.8);
+        assert!(!light.recommends_dark());
+
+        // a small bright sun raises the mean but leaves a night sky dark
+        let night = analyze(10, 255, 9);
+        assert!(night.mean > night.median);
+        assert!(night.recommends_dark());
+    }
+
+    #[test]
+    fn mean_and_median_are_weighted() {
+        let analysis =
+            LuminanceAnalysis::from_weighted_colors(&[(gray(0), 1.0), (gray(255), 3.0)]).unwrap();
+        assert!((analysis.mean - 0.75).abs() < 0.001);
+        assert!((analysis.median - 1.0).abs() < 0.001);
+
+        assert!(LuminanceAnalysis::from_weighted_colors(&[]).is_none());
+        assert!(LuminanceAnalysis::from_weighted_colors(&[(gray(0), 0.0)]).is_none());
+    }
+
+    #[test]
+    fn preferences_override_the_recommendation() {
+        let analysis = LuminanceAnalysis::from_weighted_colors(&[(gray(0), 1.0)]).unwrap();
+        assert!(analysis.is_dark(VariantPreference::Auto));
+        assert!(analysis.is_dark(VariantPreference::Dark));
+        assert!(!analysis.is_dark(VariantPreference::Light));
+    }
+}
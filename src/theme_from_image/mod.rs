@@ -0,0 +1,319 @@
+use crate::util::Oklab;
+use anyhow::{Context, Result};
+use image::{flat::SampleLayout, DynamicImage, FlatSamples, GenericImageView, Rgba, RgbaImage};
+use kmeans_colors::{get_kmeans_hamerly, Kmeans, Sort};
+use palette::{rgb::Srgba, Clamp, FromColor, IntoColor, Lab, Srgb};
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+use sampling::sample_pixels;
+use std::{borrow::Cow, path::Path};
+
+pub use dominant::*;
+pub use luminance::*;
+pub use sampling::SamplingMethod;
+pub use wallpaper::*;
+mod dominant;
+mod luminance;
+mod sampling;
+mod wallpaper;
+
+/// Color space in which image colors are clustered
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum ClusterColorSpace {
+    /// CIE Lab
+    #[default]
+    Lab,
+    /// Oklab, which separates hues more evenly
+    Oklab,
+}
+
+/// Options for extracting colors from an image
+#[derive(Clone, Debug)]
+pub struct ImageColorOptions {
+    /// number of colors to extract
+    pub clusters: usize,
+    /// maximum number of iterations of each k-means run
+    pub max_iterations: usize,
+    /// k-means stops once the centroids move less than this, in Lab units
+    pub converge: f32,
+    /// seed of each k-means run, the best run is used
+    /// the first seed also places the samples of stratified sampling
+    pub seeds: Vec<u64>,
+    /// maximum number of pixels sampled from the image, every pixel is used if None
+    pub max_samples: Option<usize>,
+    /// how pixels are sampled when the image has more than `max_samples` pixels
+    pub sampling: SamplingMethod,
+    /// color space in which colors are clustered
+    pub color_space: ClusterColorSpace,
+    /// skip pixels with less than 50% opacity
+    pub ignore_transparent: bool,
+    /// how the extracted colors are ranked
+    pub weighting: ColorWeighting,
+}
+
+impl Default for ImageColorOptions {
+    fn default() -> Self {
+        Self {
+            clusters: 8,
+            max_iterations: 20,
+            converge: 5.0,
+            seeds: vec![0, 1],
+            max_samples: Some(250_000),
+            sampling: SamplingMethod::Stratified,
+            color_space: ClusterColorSpace::Lab,
+            ignore_transparent: true,
+            weighting: ColorWeighting::Population,
+        }
+    }
+}
+
+/// Create a palette from an image with the default options
+/// The palette is sorted by how often a color occurs in the image, most often first
+pub fn theme_from_image<P: AsRef<Path>>(path: P) -> Result<Vec<Srgba>> {
+    theme_from_image_with(path, &ImageColorOptions::default())
+}
+
+/// Create a palette from an image
+/// The palette is sorted by the weighting of the options
+pub fn theme_from_image_with<P: AsRef<Path>>(
+    path: P,
+    options: &ImageColorOptions,
+) -> Result<Vec<Srgba>> {
+    let path = path.as_ref();
+    let img =
+        image::open(path).with_context(|| format!("Failed to open image {}", path.display()))?;
+    extract_colors(&img.to_rgba8(), options)
+}
+
+/// Create a palette from an image which is already loaded
+/// The palette is sorted by the weighting of the options
+pub fn theme_from_rgba_image(img: &RgbaImage, options: &ImageColorOptions) -> Result<Vec<Srgba>> {
+    extract_colors(img, options)
+}
+
+/// Create a palette from an image which is already loaded, in any pixel format
+/// The palette is sorted by the weighting of the options
+pub fn theme_from_dynamic_image(
+    img: &DynamicImage,
+    options: &ImageColorOptions,
+) -> Result<Vec<Srgba>> {
+    extract_colors(rgba8(img).as_ref(), options)
+}
+
+/// Create a palette from a buffer of 8 bit RGBA pixels, in rows from top to bottom
+/// `stride` is the length of a row in bytes, which may include padding
+/// The palette is sorted by the weighting of the options
+pub fn theme_from_rgba_buffer(
+    buf: &[u8],
+    width: u32,
+    height: u32,
+    stride: usize,
+    options: &ImageColorOptions,
+) -> Result<Vec<Srgba>> {
+    let row_len = width as usize * 4;
+    if stride < row_len {
+        anyhow::bail!("stride {stride} is shorter than a row of {width} pixels.");
+    }
+    let required = match height as usize {
+        0 => 0,
+        h => (h - 1) * stride + row_len,
+    };
+    if buf.len() < required {
+        anyhow::bail!(
+            "buffer of {} bytes is too short for {width}x{height} pixels with stride {stride}, {required} bytes are needed.",
+            buf.len()
+        );
+    }
+    let samples = FlatSamples {
+        samples: buf,
+        layout: SampleLayout {
+            channels: 4,
+            channel_stride: 1,
+            width,
+            width_stride: 4,
+            height,
+            height_stride: stride,
+        },
+        color_hint: None,
+    };
+    let view = samples
+        .as_view::<Rgba<u8>>()
+        .map_err(|e| anyhow::anyhow!("invalid RGBA buffer layout: {e:?}"))?;
+    extract_colors(&view, options)
+}
+
+/// Extract the background candidate and the ranked accent candidates of an image
+/// The weighting of the options is ignored, colors are always scored perceptually
+pub fn dominant_colors<P: AsRef<Path>>(
+    path: P,
+    options: &ImageColorOptions,
+) -> Result<DominantColors> {
+    let path = path.as_ref();
+    let img =
+        image::open(path).with_context(|| format!("Failed to open image {}", path.display()))?;
+    extract_dominant_colors(&img.to_rgba8(), options)
+}
+
+/// cluster the pixels of the image and return the centroids, ranked by the weighting of the options
+fn extract_colors<I>(img: &I, options: &ImageColorOptions) -> Result<Vec<Srgba>>
+where
+    I: GenericImageView<Pixel = Rgba<u8>> + Sync,
+{
+    match options.weighting {
+        ColorWeighting::Population => Ok(extract_clusters(img, options)?
+            .into_iter()
+            .map(|(c, _)| c)
+            .collect()),
+        ColorWeighting::Perceptual => Ok(extract_dominant_colors(img, options)?.colors()),
+    }
+}
+
+fn extract_dominant_colors<I>(img: &I, options: &ImageColorOptions) -> Result<DominantColors>
+where
+    I: GenericImageView<Pixel = Rgba<u8>> + Sync,
+{
+    DominantColors::from_clusters(&extract_clusters(img, options)?)
+        .context("the image has no colors.")
+}
+
+/// cluster the pixels of the image and return the centroids with their share of pixels, most common first
+fn extract_clusters<I>(img: &I, options: &ImageColorOptions) -> Result<Vec<(Srgba, f32)>>
+where
+    I: GenericImageView<Pixel = Rgba<u8>> + Sync,
+{
+    if options.clusters == 0 || options.clusters > u8::MAX as usize {
+        anyhow::bail!(
+            "cluster count must be between 1 and {}, not {}.",
+            u8::MAX,
+            options.clusters
+        );
+    }
+    let Some(&first_seed) = options.seeds.first() else {
+        anyhow::bail!("at least one seed is required.");
+    };
+
+    let pixels = sample_pixels(img, options.sampling, options.max_samples, first_seed);
+    let keep = |p: &&[u8; 4]| !options.ignore_transparent || p[3] >= 128;
+    let convert = |p: &[u8; 4]| -> Lab {
+        let rgb: Srgb = Srgb::new(p[0], p[1], p[2]).into_format();
+        to_cluster_space(rgb, options.color_space)
+    };
+    #[cfg(feature = "rayon")]
+    let samples: Vec<Lab> = pixels.par_iter().filter(keep).map(convert).collect();
+    #[cfg(not(feature = "rayon"))]
+    let samples: Vec<Lab> = pixels.iter().filter(keep).map(convert).collect();
+    if samples.is_empty() {
+        anyhow::bail!("the image has no opaque pixels.");
+    }
+
+    // the runs are independent, so they can run in parallel
+    let k = options.clusters.min(samples.len());
+    let run = |seed: &u64| {
+        get_kmeans_hamerly(
+            k,
+            options.max_iterations,
+            options.converge,
+            false,
+            &samples,
+            *seed,
+        )
+    };
+    #[cfg(feature = "rayon")]
+    let runs: Vec<Kmeans<Lab>> = options.seeds.par_iter().map(run).collect();
+    #[cfg(not(feature = "rayon"))]
+    let runs: Vec<Kmeans<Lab>> = options.seeds.iter().map(run).collect();
+    let result = runs
+        .into_iter()
+        .min_by(|a, b| a.score.total_cmp(&b.score))
+        .unwrap_or_else(Kmeans::new);
+
+    let mut res = Lab::sort_indexed_colors(&result.centroids, &result.indices);
+    res.sort_unstable_by(|a, b| b.percentage.total_cmp(&a.percentage));
+    Ok(res
+        .iter()
+        .filter(|x| x.percentage > 0.0)
+        .map(|x| {
+            (
+                from_cluster_space(x.centroid, options.color_space).into(),
+                x.percentage,
+            )
+        })
+        .collect())
+}
+
+/// the pixels of the image as 8 bit RGBA, converted only if they aren't already
+fn rgba8(img: &DynamicImage) -> Cow<'_, RgbaImage> {
+    match img.as_rgba8() {
+        Some(img) => Cow::Borrowed(img),
+        None => Cow::Owned(img.to_rgba8()),
+    }
+}
+
+/// Oklab coordinates are scaled to the range of Lab, so the same convergence threshold applies
+fn to_cluster_space(c: Srgb, space: ClusterColorSpace) -> Lab {
+    match space {
+        ClusterColorSpace::Lab => c.into_color(),
+        ClusterColorSpace::Oklab => {
+            let c = Oklab::from(c);
+            Lab::new(c.l * 100.0, c.a * 100.0, c.b * 100.0)
+        }
+    }
+}
+
+fn from_cluster_space(c: Lab, space: ClusterColorSpace) -> Srgb {
+    match space {
+        ClusterColorSpace::Lab => Srgb::from_color(c).clamp(),
+        ClusterColorSpace::Oklab => Srgb::from(Oklab {
+            l: c.l / 100.0,
+            a: c.a / 100.0,
+            b: c.b / 100.0,
+        })
+        .clamp(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rgba_buffer_layout_is_validated() {
+        let options = ImageColorOptions::default();
+        let buf = [0, 0, 0, 255].repeat(5);
+        assert!(theme_from_rgba_buffer(&buf, 2, 2, 7, &options).is_err());
+        assert!(theme_from_rgba_buffer(&buf[..19], 2, 2, 12, &options).is_err());
+        assert!(theme_from_rgba_buffer(&buf, 2, 2, 12, &options).is_ok());
+    }
+
+    #[test]
+    fn rgba_buffer_padding_is_ignored() {
+        // two rows of two blue pixels, each followed by a red padding pixel
+        let row = [0, 0, 255, 255, 0, 0, 255, 255, 255, 0, 0, 255];
+        let buf = [row, row].concat();
+        let colors = theme_from_rgba_buffer(&buf, 2, 2, 12, &ImageColorOptions::default()).unwrap();
+        assert!(!colors.is_empty());
+        assert!(colors.iter().all(|c| c.blue > 0.9 && c.red < 0.1));
+    }
+
+    #[test]
+    fn in_memory_images_give_the_same_palette() {
+        let img = RgbaImage::from_fn(8, 8, |x, y| match (x < 4, y < 4) {
+            (true, true) => Rgba([200, 40, 40, 255]),
+            (true, false) => Rgba([40, 40, 200, 255]),
+            _ => Rgba([230, 230, 220, 255]),
+        });
+        let options = ImageColorOptions {
+            clusters: 3,
+            ..Default::default()
+        };
+        let expected = theme_from_rgba_image(&img, &options).unwrap();
+        assert_eq!(expected.len(), 3);
+
+        let buffer = theme_from_rgba_buffer(img.as_raw(), 8, 8, 32, &options).unwrap();
+        assert_eq!(buffer, expected);
+        let rgba = theme_from_dynamic_image(&DynamicImage::ImageRgba8(img.clone()), &options);
+        assert_eq!(rgba.unwrap(), expected);
+        let rgb = DynamicImage::ImageRgb8(DynamicImage::ImageRgba8(img).to_rgb8());
+        assert_eq!(theme_from_dynamic_image(&rgb, &options).unwrap(), expected);
+    }
+}
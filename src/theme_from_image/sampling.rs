@@ -0,0 +1,151 @@
+use image::{GenericImageView, Rgba};
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+/// How pixels are picked from an image before clustering
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum SamplingMethod {
+    /// every nth pixel in row order, which may miss patterns aligned to the image
+    Stride,
+    /// the image is scaled down, averaging neighbouring pixels
+    /// small details blend into their surroundings
+    Downsample,
+    /// one pixel at a random position in each cell of an even grid
+    #[default]
+    Stratified,
+}
+
+/// pick at most `max_samples` pixels of the image, or every pixel if None
+pub(crate) fn sample_pixels<I>(
+    img: &I,
+    method: SamplingMethod,
+    max_samples: Option<usize>,
+    seed: u64,
+) -> Vec<[u8; 4]>
+where
+    I: GenericImageView<Pixel = Rgba<u8>> + Sync,
+{
+    let (width, height) = img.dimensions();
+    let len = width as usize * height as usize;
+    let max = match max_samples {
+        Some(max) if max > 0 && max < len => max,
+        _ => return img.pixels().map(|(_, _, p)| p.0).collect(),
+    };
+
+    match method {
+        SamplingMethod::Stride => img
+            .pixels()
+            .step_by(len.div_ceil(max))
+            .map(|(_, _, p)| p.0)
+            .collect(),
+        SamplingMethod::Downsample => {
+            let scale = (max as f64 / len as f64).sqrt();
+            // a side which is rounded up to one pixel leaves less room for the other
+            let w = ((width as f64 * scale) as u32).clamp(1, width);
+            let h = ((height as f64 * scale) as u32).clamp(1, height);
+            let h = (h as usize).min(max / w as usize).max(1) as u32;
+            let w = (w as usize).min(max / h as usize).max(1) as u32;
+            image::imageops::thumbnail(img, w, h)
+                .pixels()
+                .map(|p| p.0)
+                .collect()
+        }
+        SamplingMethod::Stratified => {
+            // partial cells at the edges of narrow images can add more cells than the square root suggests
+            let mut cell = ((len as f64 / max as f64).sqrt().ceil() as u32).max(1);
+            while width.div_ceil(cell) as usize * height.div_ceil(cell) as usize > max {
+                cell += 1;
+            }
+            let columns = width.div_ceil(cell);
+            let rows = height.div_ceil(cell);
+            let row = |cy: u32| -> Vec<[u8; 4]> {
+                (0..columns)
+                    .map(|cx| {
+                        let x0 = cx * cell;
+                        let y0 = cy * cell;
+                        let r = split_mix(seed ^ (u64::from(cy) << 32 | u64::from(cx)));
+                        let x = x0 + (r as u32) % cell.min(width - x0);
+                        let y = y0 + ((r >> 32) as u32) % cell.min(height - y0);
+                        img.get_pixel(x, y).0
+                    })
+                    .collect()
+            };
+            #[cfg(feature = "rayon")]
+            let rows = (0..rows).into_par_iter().flat_map_iter(row);
+            #[cfg(not(feature = "rayon"))]
+            let rows = (0..rows).flat_map(row);
+            rows.collect()
+        }
+    }
+}
+
+/// SplitMix64, a cheap hash for picking positions independently of the order cells are visited
+fn split_mix(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::RgbaImage;
+
+    #[test]
+    fn large_images_are_sampled_within_the_limit() {
+        // an 8K image, which has more than 130 times as many pixels as the limit
+        let img = RgbaImage::from_pixel(7680, 4320, Rgba([40, 60, 120, 255]));
+        let max_samples = 250_000;
+        for method in [
+            SamplingMethod::Stride,
+            SamplingMethod::Downsample,
+            SamplingMethod::Stratified,
+        ] {
+            let samples = sample_pixels(&img, method, Some(max_samples), 0);
+            assert!(
+                samples.len() <= max_samples,
+                "{method:?} took {} samples",
+                samples.len()
+            );
+            assert!(
+                samples.len() >= max_samples / 2,
+                "{method:?} took only {} samples",
+                samples.len()
+            );
+            assert!(samples.iter().all(|p| *p == [40, 60, 120, 255]));
+        }
+    }
+
+    #[test]
+    fn narrow_images_are_sampled_within_the_limit() {
+        for (width, height) in [(20_000, 1), (1, 20_000), (20_000, 3)] {
+            let img = RgbaImage::from_pixel(width, height, Rgba([0, 0, 0, 255]));
+            for method in [
+                SamplingMethod::Stride,
+                SamplingMethod::Downsample,
+                SamplingMethod::Stratified,
+            ] {
+                let samples = sample_pixels(&img, method, Some(100), 0);
+                assert!(
+                    !samples.is_empty() && samples.len() <= 100,
+                    "{method:?} took {} samples of {width}x{height}",
+                    samples.len()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn small_images_are_not_sampled() {
+        let img = RgbaImage::from_pixel(40, 30, Rgba([0, 0, 0, 255]));
+        for method in [
+            SamplingMethod::Stride,
+            SamplingMethod::Downsample,
+            SamplingMethod::Stratified,
+        ] {
+            assert_eq!(sample_pixels(&img, method, Some(250_000), 0).len(), 1200);
+            assert_eq!(sample_pixels(&img, method, None, 0).len(), 1200);
+        }
+    }
+}
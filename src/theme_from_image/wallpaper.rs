@@ -0,0 +1,387 @@
+use super::{
+    extract_colors, extract_dominant_colors, rgba8, ColorWeighting, ImageColorOptions,
+    LuminanceAnalysis, VariantPreference,
+};
+use crate::{
+    util::{ensure_contrast, is_dark, CssColor, Oklch},
+    ColorPicker, Config, Derivation, Exact, Selection, SelectionRole, Theme, ThemeConstraints,
+    DARK_PALETTE, LIGHT_PALETTE,
+};
+use anyhow::{anyhow, Context, Result};
+use image::{DynamicImage, RgbaImage};
+use palette::Srgba;
+use serde::{de::DeserializeOwned, Serialize};
+use std::{fmt, path::Path};
+
+/// minimum contrast ratio of the accent and semantic colors against the background
+const WALLPAPER_GRAPHIC_CONTRAST: f32 = 3.0;
+/// Oklch lightness by which the most elevated components are raised above the background
+const ELEVATION: f32 = 0.15;
+
+/// derive a dark or light theme from the colors of a wallpaper
+/// The theme is named after the file, e.g. `wallpaper-mountains-dark`.
+/// `constraints.lighten` is replaced by `is_dark`, see [`theme_from_colors`].
+pub fn theme_from_wallpaper<C, P>(
+    path: P,
+    is_dark: bool,
+    constraints: ThemeConstraints,
+) -> Result<Derivation<Theme<C>>>
+where
+    C: Clone + fmt::Debug + Default + Into<Srgba> + From<Srgba> + Serialize + DeserializeOwned,
+    P: AsRef<Path>,
+{
+    let (img, stem) = open_wallpaper(path.as_ref())?;
+    let colors = extract_colors(&img.to_rgba8(), &wallpaper_options())?;
+    theme_from_colors(&colors, is_dark, constraints, Some(&stem))
+}
+
+/// derive a dark or light theme from the colors of a wallpaper which is already loaded
+/// The theme is named after its accent color, e.g. `wallpaper-4682dc-dark`.
+/// `constraints.lighten` is replaced by `is_dark`, see [`theme_from_colors`].
+pub fn theme_from_wallpaper_image<C>(
+    img: &DynamicImage,
+    is_dark: bool,
+    constraints: ThemeConstraints,
+) -> Result<Derivation<Theme<C>>>
+where
+    C: Clone + fmt::Debug + Default + Into<Srgba> + From<Srgba> + Serialize + DeserializeOwned,
+{
+    let colors = extract_colors(rgba8(img).as_ref(), &wallpaper_options())?;
+    theme_from_colors(&colors, is_dark, constraints, None)
+}
+
+/// A dark and a light theme derived from the same wallpaper
+#[derive(Debug)]
+pub struct WallpaperThemes<C> {
+    /// the dark theme
+    pub dark: Derivation<Theme<C>>,
+    /// the light theme
+    pub light: Derivation<Theme<C>>,
+    /// luminance of the wallpaper
+    pub luminance: LuminanceAnalysis,
+    /// whether the dark theme is active, from the luminance unless overridden
+    pub is_dark: bool,
+}
+
+impl<C> WallpaperThemes<C> {
+    /// a config selecting both themes
+    /// the themes have to be saved before the config is used
+    pub fn config(&self) -> Config {
+        Config::new(
+            self.is_dark,
+            false,
+            self.light.derived.name.clone(),
+            self.dark.derived.name.clone(),
+        )
+    }
+}
+
+/// derive both a dark and a light theme from the colors of a wallpaper
+/// The active variant follows the luminance of the wallpaper, unless the preference overrides it.
+/// `constraints.lighten` is set for the dark theme and unset for the light theme.
+pub fn themes_from_wallpaper<C, P>(
+    path: P,
+    preference: VariantPreference,
+    constraints: ThemeConstraints,
+) -> Result<WallpaperThemes<C>>
+where
+    C: Clone + fmt::Debug + Default + Into<Srgba> + From<Srgba> + Serialize + DeserializeOwned,
+    P: AsRef<Path>,
+{
+    let (img, stem) = open_wallpaper(path.as_ref())?;
+    themes_from_rgba(&img.to_rgba8(), preference, constraints, Some(&stem))
+}
+
+/// derive both a dark and a light theme from the colors of a wallpaper which is already loaded
+/// `constraints.lighten` is set for the dark theme and unset for the light theme.
+pub fn themes_from_wallpaper_image<C>(
+    img: &DynamicImage,
+    preference: VariantPreference,
+    constraints: ThemeConstraints,
+) -> Result<WallpaperThemes<C>>
+where
+    C: Clone + fmt::Debug + Default + Into<Srgba> + From<Srgba> + Serialize + DeserializeOwned,
+{
+    themes_from_rgba(rgba8(img).as_ref(), preference, constraints, None)
+}
+
+/// the image at `path` and the name of its file without the extension
+fn open_wallpaper(path: &Path) -> Result<(DynamicImage, String)> {
+    let img =
+        image::open(path).with_context(|| format!("Failed to open image {}", path.display()))?;
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    Ok((img, stem))
+}
+
+fn themes_from_rgba<C>(
+    img: &RgbaImage,
+    preference: VariantPreference,
+    constraints: ThemeConstraints,
+    name: Option<&str>,
+) -> Result<WallpaperThemes<C>>
+where
+    C: Clone + fmt::Debug + Default + Into<Srgba> + From<Srgba> + Serialize + DeserializeOwned,
+{
+    let dominant = extract_dominant_colors(img, &wallpaper_options())?;
+    let luminance = dominant.luminance().context("the image has no colors.")?;
+    let colors = dominant.colors();
+    Ok(WallpaperThemes {
+        dark: theme_from_colors(&colors, true, constraints, name)?,
+        light: theme_from_colors(&colors, false, constraints, name)?,
+        is_dark: luminance.is_dark(preference),
+        luminance,
+    })
+}
+
+/// derive a dark or light theme from colors extracted from a wallpaper, background candidate first
+///
+/// If the most common color doesn't suit the variant, or text can't reach the requested contrast on it
+/// and on the surfaces elevated above it, the background takes its hue with the lightness of the built in palette instead.
+/// Roles are assigned against the fitted background, and the accent and semantic colors
+/// are adjusted to stand out against it.
+/// Elevated surfaces are always lightened for dark themes and darkened for light themes,
+/// so `constraints.lighten` is replaced by `is_dark`.
+/// Roles which had to be synthesized are reported as errors of the derivation.
+pub fn theme_from_colors<C>(
+    colors: &[Srgba],
+    is_dark: bool,
+    constraints: ThemeConstraints,
+    name: Option<&str>,
+) -> Result<Derivation<Theme<C>>>
+where
+    C: Clone + fmt::Debug + Default + Into<Srgba> + From<Srgba> + Serialize + DeserializeOwned,
+{
+    let grays = colors
+        .first()
+        .and_then(|bg| fit_background(*bg, is_dark, &constraints));
+    let mut assigned = match grays {
+        Some([background, ..]) => {
+            let mut colors = colors.to_vec();
+            colors[0] = background;
+            Selection::<Srgba>::from_colors(&colors)?
+        }
+        None => Selection::<Srgba>::from_colors(colors)?,
+    };
+    // containers are shades of the fitted background, which only the built in palette provides
+    if let Some([_, primary, secondary]) = grays {
+        let selection = &mut assigned.selection;
+        assigned.synthesized.retain(|role| match role {
+            SelectionRole::PrimaryContainer => {
+                selection.primary_container = primary;
+                false
+            }
+            SelectionRole::SecondaryContainer => {
+                selection.secondary_container = secondary;
+                false
+            }
+            _ => true,
+        });
+    }
+    let selection = stand_out(assigned.selection);
+    let constraints = ThemeConstraints {
+        lighten: is_dark,
+        ..constraints
+    };
+
+    let Derivation {
+        derived: mut theme,
+        mut errors,
+    } = Exact::<C>::new(selection.into_color_type(), constraints).theme_derivation();
+    for role in assigned.synthesized {
+        errors.push(anyhow!(
+            "{} was synthesized, the wallpaper has no suitable color",
+            role
+        ));
+    }
+
+    let variant = if is_dark { "dark" } else { "light" };
+    let name = match name.map(slug).filter(|s| !s.is_empty()) {
+        Some(name) => format!("wallpaper-{name}-{variant}"),
+        None => {
+            let accent: Srgba = theme.accent.base.clone().into();
+            let accent: palette::rgb::Rgba<palette::encoding::Srgb, u8> = accent.into_format();
+            format!(
+                "wallpaper-{:02x}{:02x}{:02x}-{variant}",
+                accent.red, accent.green, accent.blue
+            )
+        }
+    };
+    theme.name = name.clone();
+    theme.palette.name = name;
+
+    Ok(Derivation {
+        derived: theme,
+        errors,
+    })
+}
+
+/// background, primary and secondary container with the hue of `background` and the lightness of the built in palette,
+/// if `background` doesn't suit the variant or text can't reach the requested contrast on it
+fn fit_background(
+    background: Srgba,
+    dark: bool,
+    constraints: &ThemeConstraints,
+) -> Option<[Srgba; 3]> {
+    let bg = Oklch::from(background.color);
+    let elevated: Srgba = Oklch {
+        l: (bg.l + if dark { ELEVATION } else { -ELEVATION }).clamp(0.0, 1.0),
+        ..bg
+    }
+    .into_srgb_gamut()
+    .into();
+    let readable = |surface: Srgba| {
+        constraints
+            .contrast_model
+            .target_luminance(surface, constraints.text_contrast_ratio, dark)
+            .is_some()
+    };
+    if is_dark(background) == dark && readable(background) && readable(elevated) {
+        return None;
+    }
+
+    let defaults = if dark {
+        DARK_PALETTE.as_ref()
+    } else {
+        LIGHT_PALETTE.as_ref()
+    };
+    let relight = |gray: &CssColor| -> Srgba {
+        let gray: Srgba = gray.clone().into();
+        Oklch {
+            l: Oklch::from(gray.color).l,
+            chroma: bg.chroma.min(0.03),
+            hue: bg.hue,
+        }
+        .into_srgb_gamut()
+        .into()
+    };
+    Some([
+        relight(&defaults.gray_1),
+        relight(&defaults.gray_2),
+        relight(&defaults.gray_3),
+    ])
+}
+
+/// adjust the accent and semantic colors to stand out against the background
+fn stand_out(selection: Selection<Srgba>) -> Selection<Srgba> {
+    let bg = selection.background;
+    let stand_out = |c: Srgba| ensure_contrast(c, bg, WALLPAPER_GRAPHIC_CONTRAST);
+    Selection {
+        accent: stand_out(selection.accent),
+        destructive: stand_out(selection.destructive),
+        warning: stand_out(selection.warning),
+        success: stand_out(selection.success),
+        ..selection
+    }
+}
+
+/// wallpapers are ranked perceptually, so vivid accents win over large dull areas
+fn wallpaper_options() -> ImageColorOptions {
+    ImageColorOptions {
+        weighting: ColorWeighting::Perceptual,
+        ..Default::default()
+    }
+}
+
+/// lowercase alphanumeric words separated by dashes
+fn slug(s: &str) -> String {
+    s.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::{contrast_ratio, over};
+
+    fn rgb(red: f32, green: f32, blue: f32) -> Srgba {
+        Srgba::new(red, green, blue, 1.0)
+    }
+
+    fn synthesized(errors: &[anyhow::Error]) -> usize {
+        errors
+            .iter()
+            .filter(|e| e.to_string().contains("synthesized"))
+            .count()
+    }
+
+    #[test]
+    fn roles_are_assigned_against_the_fitted_background() {
+        // bright colors on a light beige, which all stand out once the background is dark
+        let colors = [
+            rgb(0.93, 0.88, 0.78),
+            rgb(1.0, 0.9, 0.1),
+            rgb(0.6, 0.9, 0.5),
+            rgb(0.98, 0.55, 0.45),
+            rgb(0.5, 0.75, 0.95),
+        ];
+        let dark =
+            theme_from_colors::<Srgba>(&colors, true, ThemeConstraints::default(), None).unwrap();
+        assert!(is_dark(dark.derived.background.base));
+        assert_eq!(synthesized(&dark.errors), 0, "{:?}", dark.errors);
+    }
+
+    #[test]
+    fn mid_tone_backgrounds_are_relit() {
+        let colors = [
+            rgb(0.35, 0.45, 0.6),
+            rgb(0.9, 0.3, 0.2),
+            rgb(0.95, 0.8, 0.2),
+            rgb(0.3, 0.8, 0.4),
+            rgb(0.8, 0.5, 0.9),
+        ];
+        let constraints = ThemeConstraints::default();
+        for is_dark in [true, false] {
+            let theme = theme_from_colors::<Srgba>(&colors, is_dark, constraints, None)
+                .unwrap()
+                .derived;
+            assert_ne!(theme.background.base, colors[0]);
+            for container in [&theme.background, &theme.primary, &theme.secondary] {
+                let component = &container.component;
+                for ratio in [
+                    contrast_ratio(container.on, container.base),
+                    contrast_ratio(component.on, over(component.base, container.base)),
+                ] {
+                    assert!(
+                        ratio >= constraints.text_contrast_ratio - constraints.contrast_tolerance,
+                        "{} has text with a contrast of {ratio}",
+                        theme.name
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn themes_are_named_after_the_wallpaper() {
+        let colors = [rgb(0.1, 0.1, 0.15), rgb(0.3, 0.55, 0.9)];
+        let constraints = ThemeConstraints::default();
+
+        let theme = theme_from_colors::<Srgba>(&colors, true, constraints, Some("My Wallpaper_01"))
+            .unwrap()
+            .derived;
+        assert_eq!(theme.name, "wallpaper-my-wallpaper-01-dark");
+        assert_eq!(theme.palette.name, theme.name);
+
+        // names without any letters or digits fall back to the accent color
+        for name in [None, Some("--")] {
+            let theme = theme_from_colors::<Srgba>(&colors, false, constraints, name)
+                .unwrap()
+                .derived;
+            let accent: palette::rgb::Rgba<palette::encoding::Srgb, u8> =
+                theme.accent.base.into_format();
+            assert_eq!(
+                theme.name,
+                format!(
+                    "wallpaper-{:02x}{:02x}{:02x}-light",
+                    accent.red, accent.green, accent.blue
+                )
+            );
+        }
+    }
+}
@@ -0,0 +1,39 @@
+use super::ThemeProvider;
+use crate::{Config, Theme, DARK_PALETTE, LIGHT_PALETTE};
+use palette::Srgba;
+use serde::{de::DeserializeOwned, Serialize};
+use std::fmt;
+
+/// Theme provider which reads the config and themes from the cosmic theme XDG directories
+/// The built in themes are used if their files are missing
+#[derive(Debug, Default, Clone, Copy)]
+pub struct FileThemeProvider;
+
+impl FileThemeProvider {
+    /// create a new file backed theme provider
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl<C> ThemeProvider<C> for FileThemeProvider
+where
+    C: Clone + fmt::Debug + Default + Into<Srgba> + From<Srgba> + Serialize + DeserializeOwned,
+{
+    fn config(&self) -> anyhow::Result<Config> {
+        Config::load()
+    }
+
+    fn theme(&self, name: &str) -> anyhow::Result<Theme<C>> {
+        match Theme::load_from_name(name) {
+            Ok(theme) => Ok(theme),
+            Err(_) if name == DARK_PALETTE.name() => {
+                Ok(DARK_PALETTE.clone().into_color_type().into())
+            }
+            Err(_) if name == LIGHT_PALETTE.name() => {
+                Ok(LIGHT_PALETTE.clone().into_color_type().into())
+            }
+            Err(err) => Err(err),
+        }
+    }
+}
@@ -0,0 +1,62 @@
+use super::ThemeProvider;
+use crate::{Config, Theme};
+use palette::Srgba;
+use serde::{de::DeserializeOwned, Serialize};
+use std::{collections::HashMap, fmt};
+
+/// Theme provider which keeps the config and themes in memory
+/// Useful for tests and for applications which manage their own themes
+#[derive(Debug, Default, Clone)]
+pub struct MemoryThemeProvider<C> {
+    config: Config,
+    themes: HashMap<String, Theme<C>>,
+}
+
+impl<C> MemoryThemeProvider<C>
+where
+    C: Clone + fmt::Debug + Default + Into<Srgba> + From<Srgba> + Serialize + DeserializeOwned,
+{
+    /// create a new in-memory theme provider with the given config and no themes
+    pub fn new(config: Config) -> Self {
+        Self {
+            config,
+            themes: HashMap::new(),
+        }
+    }
+
+    /// add a theme to the provider, replacing any theme with the same name
+    pub fn insert(&mut self, theme: Theme<C>) -> Option<Theme<C>> {
+        self.themes.insert(theme.name.clone(), theme)
+    }
+
+    /// remove a theme by name
+    pub fn remove(&mut self, name: &str) -> Option<Theme<C>> {
+        self.themes.remove(name)
+    }
+
+    /// replace the config
+    pub fn set_config(&mut self, config: Config) {
+        self.config = config;
+    }
+
+    /// mutable access to the config
+    pub fn config_mut(&mut self) -> &mut Config {
+        &mut self.config
+    }
+}
+
+impl<C> ThemeProvider<C> for MemoryThemeProvider<C>
+where
+    C: Clone + fmt::Debug + Default + Into<Srgba> + From<Srgba> + Serialize + DeserializeOwned,
+{
+    fn config(&self) -> anyhow::Result<Config> {
+        Ok(self.config.clone())
+    }
+
+    fn theme(&self, name: &str) -> anyhow::Result<Theme<C>> {
+        match self.themes.get(name) {
+            Some(theme) => Ok(theme.clone()),
+            None => anyhow::bail!("No theme named \"{name}\""),
+        }
+    }
+}
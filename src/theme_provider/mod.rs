@@ -1 +1,37 @@
+use crate::{Config, Theme};
+use anyhow::Context;
+use palette::Srgba;
+use serde::{de::DeserializeOwned, Serialize};
+use std::fmt;
 
+pub use file::*;
+pub use memory::*;
+#[cfg(feature = "watch")]
+pub use watcher::*;
+mod file;
+mod memory;
+#[cfg(feature = "watch")]
+mod watcher;
+
+/// Theme provider resolves the active theme from the cosmic theme config
+pub trait ThemeProvider<
+    C: Into<Srgba> + From<Srgba> + Clone + fmt::Debug + Default + Serialize + DeserializeOwned,
+>
+{
+    /// get the cosmic theme config
+    fn config(&self) -> anyhow::Result<Config>;
+
+    /// get a theme by name
+    fn theme(&self, name: &str) -> anyhow::Result<Theme<C>>;
+
+    /// get the active theme
+    /// the light or dark theme is chosen depending on the config, and converted to high contrast if enabled
+    fn active_theme(&self) -> anyhow::Result<Theme<C>> {
+        let config = self.config()?;
+        let name = config.active_name().context("No active theme configured")?;
+        let theme = self
+            .theme(&name)
+            .with_context(|| format!("Failed to load active theme \"{name}\""))?;
+        Ok(config.apply_high_contrast(theme))
+    }
+}
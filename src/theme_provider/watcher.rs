@@ -0,0 +1,249 @@
+use crate::{Config, SerializationFormat, CONFIG_NAME, NAME, THEME_DIR};
+use anyhow::Context;
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use std::{
+    collections::BTreeSet,
+    fmt,
+    path::{Path, PathBuf},
+    sync::mpsc::{self, Receiver, RecvTimeoutError},
+    thread,
+    time::Duration,
+};
+
+/// default time to wait for writes to settle before emitting events
+pub const DEFAULT_DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// A change to the cosmic theme config or theme directory
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ThemeEvent {
+    /// the name of the active theme changed
+    ActiveThemeChanged(String),
+    /// dark mode was turned on or off
+    DarkModeChanged(bool),
+    /// high contrast mode was turned on or off
+    HighContrastChanged(bool),
+    /// a theme file was created or edited
+    ThemeModified(String),
+    /// a theme file was deleted
+    ThemeDeleted(String),
+}
+
+/// Watches the cosmic theme config and theme directory and emits [`ThemeEvent`]s
+/// Events stop when the watcher is dropped
+pub struct ThemeWatcher {
+    _watcher: RecommendedWatcher,
+}
+
+impl fmt::Debug for ThemeWatcher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ThemeWatcher").finish_non_exhaustive()
+    }
+}
+
+impl ThemeWatcher {
+    /// watch for changes, calling `callback` with each event
+    pub fn new<F>(callback: F) -> anyhow::Result<Self>
+    where
+        F: FnMut(ThemeEvent) + Send + 'static,
+    {
+        Self::with_debounce(DEFAULT_DEBOUNCE, callback)
+    }
+
+    /// watch for changes, sending each event through the returned channel
+    pub fn channel() -> anyhow::Result<(Self, Receiver<ThemeEvent>)> {
+        let (tx, rx) = mpsc::channel();
+        let watcher = Self::new(move |event| {
+            let _ = tx.send(event);
+        })?;
+        Ok((watcher, rx))
+    }
+
+    /// watch for changes, waiting until no file has changed for `debounce` before emitting events
+    /// this avoids reading partially written files
+    pub fn with_debounce<F>(debounce: Duration, mut callback: F) -> anyhow::Result<Self>
+    where
+        F: FnMut(ThemeEvent) + Send + 'static,
+    {
+        let config_dir = directories::ProjectDirs::from_path(PathBuf::from(NAME))
+            .context("Failed to find project directory.")?
+            .config_dir()
+            .to_path_buf();
+        let theme_path: PathBuf = [NAME, THEME_DIR].iter().collect();
+        let theme_dir = directories::ProjectDirs::from_path(theme_path)
+            .context("Failed to find project directory.")?
+            .config_dir()
+            .to_path_buf();
+        std::fs::create_dir_all(&config_dir)?;
+        std::fs::create_dir_all(&theme_dir)?;
+
+        // a missing config is the default config, so creating it emits the changes from the default
+        let mut config = Config::find_config_file()
+            .ok()
+            .flatten()
+            .and_then(|path| read_config(&path))
+            .unwrap_or_default();
+
+        let (tx, rx) = mpsc::channel();
+        let mut watcher =
+            notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+                if let Ok(event) = res {
+                    let _ = tx.send(event.paths);
+                }
+            })?;
+        watcher.watch(&config_dir, RecursiveMode::NonRecursive)?;
+        watcher.watch(&theme_dir, RecursiveMode::NonRecursive)?;
+
+        thread::spawn(move || {
+            let mut pending = BTreeSet::<PathBuf>::new();
+            loop {
+                let paths = if pending.is_empty() {
+                    match rx.recv() {
+                        Ok(paths) => paths,
+                        Err(_) => break,
+                    }
+                } else {
+                    match rx.recv_timeout(debounce) {
+                        Ok(paths) => paths,
+                        Err(RecvTimeoutError::Timeout) => {
+                            for path in std::mem::take(&mut pending) {
+                                if is_config(&path, &config_dir) {
+                                    config_events(&path, &mut config, &mut callback);
+                                } else if path.parent() == Some(theme_dir.as_path()) {
+                                    theme_events(&path, &mut callback);
+                                }
+                            }
+                            continue;
+                        }
+                        Err(RecvTimeoutError::Disconnected) => break,
+                    }
+                };
+                pending.extend(paths);
+            }
+        });
+
+        Ok(Self { _watcher: watcher })
+    }
+}
+
+fn read_config(path: &Path) -> Option<Config> {
+    Config::load_from(&path).ok()
+}
+
+/// the config may be saved in any supported format
+fn is_config(path: &Path, config_dir: &Path) -> bool {
+    path.parent() == Some(config_dir)
+        && path.file_stem().is_some_and(|stem| stem == CONFIG_NAME)
+        && is_supported(path)
+}
+
+fn is_supported(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| {
+            SerializationFormat::ALL
+                .iter()
+                .any(|format| format.extension() == ext)
+        })
+}
+
+fn config_events<F: FnMut(ThemeEvent)>(path: &Path, config: &mut Config, callback: &mut F) {
+    // a config which fails to parse is most likely still being written
+    let new = match read_config(path) {
+        Some(new) => new,
+        None => return,
+    };
+
+    for event in config_changes(config, &new) {
+        callback(event);
+    }
+    *config = new;
+}
+
+fn config_changes(old: &Config, new: &Config) -> Vec<ThemeEvent> {
+    let mut events = Vec::new();
+    if old.is_dark != new.is_dark {
+        events.push(ThemeEvent::DarkModeChanged(new.is_dark));
+    }
+    if old.is_high_contrast != new.is_high_contrast {
+        events.push(ThemeEvent::HighContrastChanged(new.is_high_contrast));
+    }
+    match new.active_name() {
+        Some(name) if old.active_name().as_ref() != Some(&name) => {
+            events.push(ThemeEvent::ActiveThemeChanged(name))
+        }
+        _ => {}
+    }
+    events
+}
+
+fn theme_events<F: FnMut(ThemeEvent)>(path: &Path, callback: &mut F) {
+    // themes may be saved in any supported format
+    let is_theme = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| {
+            SerializationFormat::ALL
+                .iter()
+                .any(|format| format.extension() == ext)
+        });
+    if !is_theme {
+        return;
+    }
+    let name = match path.file_stem().and_then(|s| s.to_str()) {
+        Some(name) => name.to_string(),
+        None => return,
+    };
+
+    if path.exists() {
+        callback(ThemeEvent::ThemeModified(name));
+    } else {
+        callback(ThemeEvent::ThemeDeleted(name));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn changes_are_compared_against_the_default_config() {
+        assert!(config_changes(&Config::default(), &Config::default()).is_empty());
+
+        let config = Config {
+            is_dark: false,
+            ..Default::default()
+        };
+        assert_eq!(
+            config_changes(&Config::default(), &config),
+            vec![
+                ThemeEvent::DarkModeChanged(false),
+                ThemeEvent::ActiveThemeChanged("cosmic-light".to_string()),
+            ]
+        );
+
+        let high_contrast = Config {
+            is_high_contrast: true,
+            dark: "custom".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(
+            config_changes(&Config::default(), &high_contrast),
+            vec![
+                ThemeEvent::HighContrastChanged(true),
+                ThemeEvent::ActiveThemeChanged("custom".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn config_files_are_recognized() {
+        let dir = Path::new("/config");
+        assert!(is_config(&dir.join(format!("{CONFIG_NAME}.ron")), dir));
+        assert!(!is_config(&dir.join(format!("{CONFIG_NAME}.txt")), dir));
+        assert!(!is_config(&dir.join("other.ron"), dir));
+        assert!(!is_config(
+            &Path::new("/elsewhere").join(format!("{CONFIG_NAME}.ron")),
+            dir
+        ));
+    }
+}
@@ -1,5 +1,5 @@
 use csscolorparser::Color;
-use palette::Srgba;
+use palette::{Clamp, FromColor, IntoColor, Lch, LinSrgb, RelativeContrast, Srgb, Srgba, Xyz};
 use serde::{Deserialize, Serialize};
 
 /// utility wrapper for serializing and deserializing colors with arbitrary CSS
@@ -57,3 +57,417 @@ fn alpha_over(a: f32, b: f32) -> f32 {
 fn c_over(a: f32, b: f32, a_alpha: f32, b_alpha: f32, o_alpha: f32) -> f32 {
     a * a_alpha + b * b_alpha * (1.0 - a_alpha) / o_alpha
 }
+
+/// relative luminance of the color as defined by WCAG 2, ignoring alpha
+pub fn luminance<C: Into<Srgba>>(c: C) -> f32 {
+    let c: Srgba = c.into();
+    let xyz: Xyz = c.color.into_color();
+    xyz.y
+}
+
+/// WCAG 2 contrast ratio of `fg` on top of `bg`, translucent foregrounds are composited over `bg` first
+pub fn contrast_ratio<A: Into<Srgba>, B: Into<Srgba>>(fg: A, bg: B) -> f32 {
+    let bg: Srgba = bg.into();
+    let fg = over(fg, bg);
+    fg.color.get_contrast_ratio(&bg.color)
+}
+
+/// whether white text has more contrast on the color than black text
+pub fn is_dark<C: Into<Srgba>>(c: C) -> bool {
+    let c: Srgba = c.into();
+    contrast_ratio(Srgba::new(1.0, 1.0, 1.0, 1.0), c)
+        > contrast_ratio(Srgba::new(0.0, 0.0, 0.0, 1.0), c)
+}
+
+/// APCA lightness contrast (Lc) of `fg` text on top of `bg`, translucent foregrounds are composited over `bg` first
+/// positive for dark text on a light background, negative for light text on a dark background
+pub fn apca_contrast<A: Into<Srgba>, B: Into<Srgba>>(fg: A, bg: B) -> f32 {
+    let bg: Srgba = bg.into();
+    let fg = over(fg, bg);
+    let y_fg = apca_luminance(fg);
+    let y_bg = apca_luminance(bg);
+    if (y_bg - y_fg).abs() < 0.0005 {
+        return 0.0;
+    }
+
+    let lc = if y_bg > y_fg {
+        let sapc = (y_bg.powf(0.56) - y_fg.powf(0.57)) * 1.14;
+        if sapc < 0.1 {
+            0.0
+        } else {
+            sapc - 0.027
+        }
+    } else {
+        let sapc = (y_bg.powf(0.65) - y_fg.powf(0.62)) * 1.14;
+        if sapc > -0.1 {
+            0.0
+        } else {
+            sapc + 0.027
+        }
+    };
+    lc * 100.0
+}
+
+/// screen luminance estimate used by APCA, with the soft clamp for near black colors
+fn apca_luminance(c: Srgba) -> f32 {
+    let y =
+        0.2126729 * c.red.powf(2.4) + 0.7151522 * c.green.powf(2.4) + 0.0721750 * c.blue.powf(2.4);
+    if y < 0.022 {
+        y + (0.022 - y).powf(1.414)
+    } else {
+        y
+    }
+}
+
+/// Model used to measure the contrast between two colors
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum ContrastModel {
+    /// WCAG 2 contrast ratio, from 1 to 21
+    #[default]
+    Wcag2,
+    /// APCA lightness contrast, absolute Lc value from 0 to about 106
+    Apca,
+}
+
+impl ContrastModel {
+    /// how far a derived contrast may fall short of its target, as colors are only found within a tolerance
+    pub(crate) fn tolerance(&self) -> f32 {
+        match self {
+            Self::Wcag2 => 0.01,
+            Self::Apca => 0.1,
+        }
+    }
+
+    /// default contrast of dividers, which only need to be noticeable
+    pub(crate) fn divider_contrast(&self) -> f32 {
+        match self {
+            Self::Wcag2 => 1.51,
+            Self::Apca => 15.0,
+        }
+    }
+
+    /// contrast of `fg` on top of `bg` measured with this model
+    /// APCA values are absolute, so they can be compared with targets regardless of polarity
+    pub fn contrast<A: Into<Srgba>, B: Into<Srgba>>(&self, fg: A, bg: B) -> f32 {
+        match self {
+            Self::Wcag2 => contrast_ratio(fg, bg),
+            Self::Apca => apca_contrast(fg, bg).abs(),
+        }
+    }
+
+    /// luminance of the color as seen by this model, ignoring alpha
+    /// WCAG 2 relative luminance, or the soft clamped APCA screen luminance
+    pub fn luminance<C: Into<Srgba>>(&self, c: C) -> f32 {
+        match self {
+            Self::Wcag2 => luminance(c),
+            Self::Apca => apca_luminance(c.into()),
+        }
+    }
+
+    /// luminance, as seen by this model, which a color lighter or darker than `bg` needs to have exactly `contrast` against it
+    /// computed directly from the contrast formula, returns None if no such color exists
+    pub fn target_luminance<B: Into<Srgba>>(
+        &self,
+        bg: B,
+        contrast: f32,
+        lighter: bool,
+    ) -> Option<f32> {
+        let y_bg = self.luminance(bg);
+        let (y, min) = match self {
+            Self::Wcag2 if lighter => (contrast * (y_bg + 0.05) - 0.05, 0.0),
+            Self::Wcag2 => ((y_bg + 0.05) / contrast - 0.05, 0.0),
+            Self::Apca => {
+                let sapc = (contrast / 100.0 + 0.027) / 1.14;
+                let y = if lighter {
+                    y_bg.powf(0.65) + sapc
+                } else {
+                    y_bg.powf(0.56) - sapc
+                };
+                if y < 0.0 {
+                    return None;
+                }
+                let exp = if lighter { 0.62 } else { 0.57 };
+                // black is soft clamped to this luminance
+                (y.powf(1.0 / exp), 0.022_f32.powf(1.414))
+            }
+        };
+        // allow for rounding in the coefficients of white
+        if y < min || y > 1.0 + 1e-4 {
+            None
+        } else {
+            Some(y.min(1.0))
+        }
+    }
+
+    /// opaque gray with the given luminance, as seen by this model
+    pub fn gray(&self, y: f32) -> Srgba {
+        let gray = match self {
+            Self::Wcag2 => {
+                // inverse of the srgb transfer function
+                let y = y.clamp(0.0, 1.0);
+                if y <= 0.0031308 {
+                    y * 12.92
+                } else {
+                    1.055 * y.powf(1.0 / 2.4) - 0.055
+                }
+            }
+            Self::Apca => {
+                // undo the soft clamp, which is monotonic below the threshold
+                let mut y_lin = y;
+                if y < 0.022 {
+                    let (mut l, mut r) = (0.0_f32, 0.022);
+                    for _ in 0..24 {
+                        let mid = (l + r) / 2.0;
+                        if mid + (0.022 - mid).powf(1.414) < y {
+                            l = mid;
+                        } else {
+                            r = mid;
+                        }
+                    }
+                    y_lin = (l + r) / 2.0;
+                }
+                y_lin.clamp(0.0, 1.0).powf(1.0 / 2.4)
+            }
+        };
+        Srgba::new(gray, gray, gray, 1.0)
+    }
+}
+
+/// adjust the lightness of `fg` until it has a contrast ratio of at least `ratio` against `bg`
+/// hue and chroma are kept where possible, and the result is opaque
+/// falls back to black or white, whichever has more contrast, if the ratio can't be reached
+pub fn ensure_contrast<A: Into<Srgba>, B: Into<Srgba>>(fg: A, bg: B, ratio: f32) -> Srgba {
+    let bg: Srgba = bg.into();
+    let fg = over(fg, bg);
+    if contrast_ratio(fg, bg) >= ratio {
+        return fg;
+    }
+
+    let white = Srgba::new(1.0, 1.0, 1.0, 1.0);
+    let black = Srgba::new(0.0, 0.0, 0.0, 1.0);
+    let lighter_first = luminance(fg) >= luminance(bg);
+    let directions = if lighter_first {
+        [true, false]
+    } else {
+        [false, true]
+    };
+    for lighten in directions {
+        let extreme = if lighten { white } else { black };
+        if contrast_ratio(extreme, bg) < ratio {
+            continue;
+        }
+        let lch: Lch = fg.color.into_color();
+        let (mut l, mut r) = if lighten {
+            (lch.l, 100.0)
+        } else {
+            (0.0, lch.l)
+        };
+        // bisect the lightness, keeping the bound which is known to satisfy the ratio
+        for _ in 0..32 {
+            let mid = (l + r) / 2.0;
+            let mut guess = lch;
+            guess.l = mid;
+            let guess = Srgba::from(Srgb::from_color(guess).clamp());
+            let satisfied = contrast_ratio(guess, bg) >= ratio;
+            if satisfied == lighten {
+                r = mid;
+            } else {
+                l = mid;
+            }
+        }
+        let mut res = lch;
+        res.l = if lighten { r } else { l };
+        let res = Srgba::from(Srgb::from_color(res).clamp());
+        if contrast_ratio(res, bg) >= ratio {
+            return res;
+        }
+        return extreme;
+    }
+
+    if contrast_ratio(white, bg) >= contrast_ratio(black, bg) {
+        white
+    } else {
+        black
+    }
+}
+
+/// Oklab color, a perceptual color space which keeps hue more uniform than Lab
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Oklab {
+    /// perceived lightness, from 0 to 1
+    pub l: f32,
+    /// green to red axis
+    pub a: f32,
+    /// blue to yellow axis
+    pub b: f32,
+}
+
+/// Oklch color, the polar form of Oklab
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Oklch {
+    /// perceived lightness, from 0 to 1
+    pub l: f32,
+    /// chroma, from 0 to about 0.37 for srgb colors
+    pub chroma: f32,
+    /// hue in degrees
+    pub hue: f32,
+}
+
+impl From<Srgb> for Oklab {
+    // coefficients as published with Oklab
+    #[allow(clippy::excessive_precision)]
+    fn from(c: Srgb) -> Self {
+        let c = c.into_linear();
+        let l = (0.4122214708 * c.red + 0.5363325363 * c.green + 0.0514459929 * c.blue).cbrt();
+        let m = (0.2119034982 * c.red + 0.6806995451 * c.green + 0.1073969566 * c.blue).cbrt();
+        let s = (0.0883024619 * c.red + 0.2817188376 * c.green + 0.6299787005 * c.blue).cbrt();
+        Self {
+            l: 0.2104542553 * l + 0.7936177850 * m - 0.0040720468 * s,
+            a: 1.9779984951 * l - 2.4285922050 * m + 0.4505937099 * s,
+            b: 0.0259040371 * l + 0.7827717662 * m - 0.8086757660 * s,
+        }
+    }
+}
+
+/// the result may be outside of the srgb gamut
+impl From<Oklab> for Srgb {
+    #[allow(clippy::excessive_precision)]
+    fn from(c: Oklab) -> Self {
+        let l = (c.l + 0.3963377774 * c.a + 0.2158037573 * c.b).powi(3);
+        let m = (c.l - 0.1055613458 * c.a - 0.0638541728 * c.b).powi(3);
+        let s = (c.l - 0.0894841775 * c.a - 1.2914855480 * c.b).powi(3);
+        Srgb::from_linear(LinSrgb::new(
+            4.0767416621 * l - 3.3077115913 * m + 0.2309699292 * s,
+            -1.2684380046 * l + 2.6097574011 * m - 0.3413193965 * s,
+            -0.0041960863 * l - 0.7034186147 * m + 1.7076147010 * s,
+        ))
+    }
+}
+
+impl From<Oklab> for Oklch {
+    fn from(c: Oklab) -> Self {
+        Self {
+            l: c.l,
+            chroma: c.a.hypot(c.b),
+            hue: c.b.atan2(c.a).to_degrees().rem_euclid(360.0),
+        }
+    }
+}
+
+impl From<Oklch> for Oklab {
+    fn from(c: Oklch) -> Self {
+        let (sin, cos) = c.hue.to_radians().sin_cos();
+        Self {
+            l: c.l,
+            a: c.chroma * cos,
+            b: c.chroma * sin,
+        }
+    }
+}
+
+impl From<Srgb> for Oklch {
+    fn from(c: Srgb) -> Self {
+        Oklab::from(c).into()
+    }
+}
+
+/// the result may be outside of the srgb gamut
+impl From<Oklch> for Srgb {
+    fn from(c: Oklch) -> Self {
+        Oklab::from(c).into()
+    }
+}
+
+impl Oklch {
+    /// convert to srgb, reducing the chroma until the color fits in the gamut so lightness and hue are kept
+    pub fn into_srgb_gamut(self) -> Srgb {
+        let in_gamut = |c: Srgb| {
+            [c.red, c.green, c.blue]
+                .iter()
+                .all(|v| (-1e-4..=1.0 + 1e-4).contains(v))
+        };
+        let c = Srgb::from(self);
+        if in_gamut(c) {
+            return c.clamp();
+        }
+
+        let (mut l, mut r) = (0.0, self.chroma);
+        for _ in 0..24 {
+            let mid = (l + r) / 2.0;
+            if in_gamut(Srgb::from(Self {
+                chroma: mid,
+                ..self
+            })) {
+                l = mid;
+            } else {
+                r = mid;
+            }
+        }
+        Srgb::from(Self { chroma: l, ..self }).clamp()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GRAYS: [f32; 5] = [0.0, 0.1, 0.5, 0.9, 1.0];
+
+    #[test]
+    fn target_luminance_has_the_requested_contrast() {
+        for (model, contrasts, tolerance) in [
+            (ContrastModel::Wcag2, [1.1, 3.0, 4.5, 7.0], 0.01),
+            (ContrastModel::Apca, [8.0, 30.0, 60.0, 75.0], 0.1),
+        ] {
+            for v in GRAYS {
+                let bg = Srgba::new(v, v, v, 1.0);
+                for contrast in contrasts {
+                    for lighter in [true, false] {
+                        if let Some(y) = model.target_luminance(bg, contrast, lighter) {
+                            let measured = model.contrast(model.gray(y), bg);
+                            assert!(
+                                (measured - contrast).abs() <= tolerance,
+                                "{model:?} on {v}: requested {contrast}, measured {measured}"
+                            );
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn unreachable_target_luminance_is_none() {
+        let white = Srgba::new(1.0, 1.0, 1.0, 1.0);
+        let gray = Srgba::new(0.5, 0.5, 0.5, 1.0);
+        assert!(ContrastModel::Wcag2
+            .target_luminance(white, 1.5, true)
+            .is_none());
+        assert!(ContrastModel::Wcag2
+            .target_luminance(gray, 7.0, true)
+            .is_none());
+        assert!(ContrastModel::Apca
+            .target_luminance(white, 30.0, true)
+            .is_none());
+    }
+
+    #[test]
+    fn ensure_contrast_meets_the_ratio() {
+        let colors = [
+            Srgba::new(0.58, 0.922, 0.922, 1.0),
+            Srgba::new(0.8, 0.2, 0.3, 1.0),
+            Srgba::new(0.5, 0.5, 0.5, 0.5),
+        ];
+        for v in GRAYS {
+            let bg = Srgba::new(v, v, v, 1.0);
+            for fg in colors {
+                for ratio in [3.0, 4.5, 7.0] {
+                    let c = ensure_contrast(fg, bg, ratio);
+                    let best = contrast_ratio(Srgba::new(1.0, 1.0, 1.0, 1.0), bg)
+                        .max(contrast_ratio(Srgba::new(0.0, 0.0, 0.0, 1.0), bg));
+                    assert!(contrast_ratio(c, bg) >= ratio.min(best));
+                }
+            }
+        }
+    }
+}